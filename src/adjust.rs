@@ -0,0 +1,443 @@
+use crate::color;
+use crate::stats;
+use crate::value;
+
+/// A correction measured on the image it applies to, read back from the GPU at every render
+pub enum Adjustment {
+    /// Stretch levels so that the given percentages of pixels clip to black and white, each
+    /// channel on its own when `per_channel`, which also neutralizes color casts
+    Levels {
+        clip_shadows: f32,
+        clip_highlights: f32,
+        per_channel: bool,
+    },
+    /// Gray-world white balance, scaling channels in linear light so their averages match
+    WhiteBalance { amount: f32 },
+    /// Contrast limited adaptive histogram equalization of luminance over a grid of 1 to 64
+    /// tiles per side
+    Equalize {
+        tiles: u32,
+        clip_limit: f32,
+        amount: f32,
+    },
+    /// Transfer the colors of a reference image, mixed with the original by `strength`
+    MatchColor { reference: Reference, strength: f32 },
+}
+
+/// Statistics of a reference image, measured once when loading
+pub enum Reference {
+    /// Mean and standard deviation of each CIE L*a*b* channel, after Reinhard et al.
+    /// "Color Transfer between Images"
+    Lab { mean: [f32; 3], stddev: [f32; 3] },
+    /// Cumulative histogram of each RGB channel
+    Histogram { cdf: [Vec<f64>; 3] },
+}
+
+impl Reference {
+    pub fn lab(image: &image::RgbaImage) -> Self {
+        let (mean, stddev) = lab_stats(image);
+        Reference::Lab { mean, stddev }
+    }
+
+    pub fn histogram(image: &image::RgbaImage) -> Self {
+        Reference::Histogram { cdf: cdfs(image) }
+    }
+}
+
+/// Uniforms measured from an image, and the lookup table bound as `u_lut` for `Equalize` and
+/// histogram matching
+pub struct Analysis {
+    pub uniforms: Vec<(String, value::UniformValue)>,
+    pub lut: Option<three_d::Texture2D>,
+}
+
+impl Adjustment {
+    /// Measure an image read back with rows from the top
+    pub fn analyze(&self, context: &three_d::Context, image: &image::RgbaImage) -> Analysis {
+        match self {
+            Adjustment::Levels {
+                clip_shadows,
+                clip_highlights,
+                per_channel,
+            } => {
+                let stats = stats::analyze(image);
+                let channels = [&stats.red, &stats.green, &stats.blue];
+                let (low, high) = if *per_channel {
+                    let bounds = channels.map(|channel| {
+                        clip_bounds(&channel.histogram, *clip_shadows, *clip_highlights)
+                    });
+                    (bounds.map(|(low, _)| low), bounds.map(|(_, high)| high))
+                } else {
+                    let mut histogram = vec![0u64; 256];
+                    for channel in channels {
+                        for (sum, n) in histogram.iter_mut().zip(channel.histogram.iter()) {
+                            *sum += n;
+                        }
+                    }
+                    let (low, high) = clip_bounds(&histogram, *clip_shadows, *clip_highlights);
+                    ([low; 3], [high; 3])
+                };
+                Analysis {
+                    uniforms: vec![
+                        ("u_low".to_string(), low.into()),
+                        ("u_high".to_string(), high.into()),
+                    ],
+                    lut: None,
+                }
+            }
+            Adjustment::WhiteBalance { amount } => {
+                let mut sums = [0.0f64; 3];
+                let mut count = 0.0;
+                for pixel in image.pixels().filter(|pixel| pixel.0[3] > 0) {
+                    for (sum, value) in sums.iter_mut().zip(pixel.0) {
+                        *sum += color::srgb_to_linear(value as f64 / 255.0);
+                    }
+                    count += 1.0;
+                }
+                let means = sums.map(|sum| sum / f64::max(count, 1.0));
+                // Keep luminance, so only the cast changes
+                let gray = color::luminance(means);
+                let gain = means.map(|mean| {
+                    let gain = if mean > 0.0 { gray / mean } else { 1.0 };
+                    (1.0 + (gain - 1.0) * *amount as f64) as f32
+                });
+                Analysis {
+                    uniforms: vec![("u_gain".to_string(), gain.into())],
+                    lut: None,
+                }
+            }
+            Adjustment::Equalize {
+                tiles,
+                clip_limit,
+                amount,
+            } => Analysis {
+                uniforms: vec![
+                    ("u_tiles".to_string(), (*tiles as f32).into()),
+                    ("u_amount".to_string(), (*amount).into()),
+                ],
+                lut: Some(equalize_lut(context, image, *tiles, *clip_limit)),
+            },
+            Adjustment::MatchColor {
+                reference: Reference::Lab { mean, stddev },
+                strength,
+            } => {
+                let (source_mean, source_stddev) = lab_stats(image);
+                Analysis {
+                    uniforms: vec![
+                        ("u_source_mean".to_string(), source_mean.into()),
+                        ("u_source_stddev".to_string(), source_stddev.into()),
+                        ("u_reference_mean".to_string(), (*mean).into()),
+                        ("u_reference_stddev".to_string(), (*stddev).into()),
+                        ("u_strength".to_string(), (*strength).into()),
+                    ],
+                    lut: None,
+                }
+            }
+            Adjustment::MatchColor {
+                reference: Reference::Histogram { cdf },
+                strength,
+            } => Analysis {
+                uniforms: vec![("u_strength".to_string(), (*strength).into())],
+                lut: Some(histogram_lut(context, &cdfs(image), cdf)),
+            },
+        }
+    }
+}
+
+/// Levels from 0 to 1 past which the given percentages of pixels fall
+fn clip_bounds(histogram: &[u64], clip_shadows: f32, clip_highlights: f32) -> (f32, f32) {
+    let count: u64 = histogram.iter().sum();
+    let limit = |percentage: f32| (count as f64 * percentage.max(0.0) as f64 / 100.0) as u64;
+
+    let mut seen = 0;
+    let low = (0..256)
+        .find(|level| {
+            seen += histogram[*level];
+            seen > limit(clip_shadows)
+        })
+        .unwrap_or(0);
+    seen = 0;
+    let high = (0..256)
+        .rev()
+        .find(|level| {
+            seen += histogram[*level];
+            seen > limit(clip_highlights)
+        })
+        .unwrap_or(255);
+
+    if low >= high {
+        // Flat image, nothing to stretch
+        (0.0, 1.0)
+    } else {
+        (low as f32 / 255.0, high as f32 / 255.0)
+    }
+}
+
+/// Equalized levels of each tile as rows of a 256 wide texture, see `equalize_rows`
+fn equalize_lut(
+    context: &three_d::Context,
+    image: &image::RgbaImage,
+    tiles: u32,
+    clip_limit: f32,
+) -> three_d::Texture2D {
+    three_d::Texture2D::new(
+        context,
+        &three_d::CpuTexture {
+            data: three_d::TextureData::RF32(equalize_rows(image, tiles, clip_limit)),
+            width: 256,
+            height: tiles * tiles,
+            min_filter: three_d::Interpolation::Nearest,
+            mag_filter: three_d::Interpolation::Nearest,
+            mip_map_filter: None,
+            wrap_s: three_d::Wrapping::ClampToEdge,
+            wrap_t: three_d::Wrapping::ClampToEdge,
+            ..Default::default()
+        },
+    )
+}
+
+/// Cumulative luminance histogram of each tile, clipped and redistributed, then stretched so
+/// the lowest level present maps to 0. Tiles are counted from the bottom-left corner like
+/// `gl_FragCoord`, 256 levels each.
+fn equalize_rows(image: &image::RgbaImage, tiles: u32, clip_limit: f32) -> Vec<f32> {
+    let (width, height) = image.dimensions();
+    let mut histograms = vec![[0.0f32; 256]; (tiles * tiles) as usize];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a == 0 {
+            continue;
+        }
+        let luminance = color::luminance([r, g, b].map(f64::from));
+        let tile_x = x * tiles / width;
+        let tile_y = (height - 1 - y) * tiles / height;
+        histograms[(tile_y * tiles + tile_x) as usize][luminance.round() as usize] += 1.0;
+    }
+
+    let mut data = Vec::with_capacity(histograms.len() * 256);
+    for histogram in histograms.iter_mut() {
+        let count: f32 = histogram.iter().sum();
+        // Limit the slope of the mapping, spreading the clipped counts over all levels
+        if clip_limit > 0.0 {
+            let limit = clip_limit * count / 256.0;
+            let mut excess = 0.0;
+            for n in histogram.iter_mut() {
+                excess += (*n - limit).max(0.0);
+                *n = n.min(limit);
+            }
+            for n in histogram.iter_mut() {
+                *n += excess / 256.0;
+            }
+        }
+        let lowest = histogram.iter().copied().find(|n| *n > 0.0).unwrap_or(0.0);
+        if count - lowest <= 0.0 {
+            // Empty or flat tile, nothing to spread
+            data.extend((0..256).map(|level| level as f32 / 255.0));
+            continue;
+        }
+        let mut cumulative = 0.0;
+        data.extend(histogram.iter().map(|n| {
+            cumulative += n;
+            ((cumulative - lowest) / (count - lowest)).max(0.0)
+        }));
+    }
+    data
+}
+
+/// Mean and standard deviation of the CIE L*a*b* channels of the visible pixels
+fn lab_stats(image: &image::RgbaImage) -> ([f32; 3], [f32; 3]) {
+    let mut sums = [0.0f64; 3];
+    let mut squares = [0.0f64; 3];
+    let mut count = 0.0;
+    for pixel in image.pixels().filter(|pixel| pixel.0[3] > 0) {
+        let lab = color::lab([pixel.0[0], pixel.0[1], pixel.0[2]].map(|c| c as f64 / 255.0));
+        for channel in 0..3 {
+            sums[channel] += lab[channel];
+            squares[channel] += lab[channel] * lab[channel];
+        }
+        count += 1.0;
+    }
+    let count = f64::max(count, 1.0);
+    let mean = sums.map(|sum| sum / count);
+    let mut stddev = [0.0; 3];
+    for channel in 0..3 {
+        stddev[channel] = (squares[channel] / count - mean[channel].powi(2))
+            .max(0.0)
+            .sqrt() as f32;
+    }
+    (mean.map(|m| m as f32), stddev)
+}
+
+/// Cumulative histograms of the red, green and blue channels of the visible pixels, from 0 to 1
+fn cdfs(image: &image::RgbaImage) -> [Vec<f64>; 3] {
+    let stats = stats::analyze(image);
+    [&stats.red, &stats.green, &stats.blue].map(|channel| {
+        let count = channel.histogram.iter().sum::<u64>().max(1) as f64;
+        let mut cumulative = 0;
+        channel
+            .histogram
+            .iter()
+            .map(|n| {
+                cumulative += n;
+                cumulative as f64 / count
+            })
+            .collect()
+    })
+}
+
+/// Upload `matched_levels` as a 256×1 texture with one channel per color
+fn histogram_lut(
+    context: &three_d::Context,
+    source: &[Vec<f64>; 3],
+    reference: &[Vec<f64>; 3],
+) -> three_d::Texture2D {
+    let data = matched_levels(source, reference);
+    three_d::Texture2D::new(
+        context,
+        &three_d::CpuTexture {
+            data: three_d::TextureData::RgbaF32(data),
+            width: 256,
+            height: 1,
+            min_filter: three_d::Interpolation::Nearest,
+            mag_filter: three_d::Interpolation::Nearest,
+            mip_map_filter: None,
+            wrap_s: three_d::Wrapping::ClampToEdge,
+            wrap_t: three_d::Wrapping::ClampToEdge,
+            ..Default::default()
+        },
+    )
+}
+
+/// Map each level so its cumulative frequency in the image matches the reference
+fn matched_levels(source: &[Vec<f64>; 3], reference: &[Vec<f64>; 3]) -> Vec<[f32; 4]> {
+    (0..256)
+        .map(|level| {
+            let mut mapped = [1.0f32; 4];
+            for channel in 0..3 {
+                let (source, reference) = (&source[channel], &reference[channel]);
+                let p = source[level];
+                let upper = reference.iter().position(|c| *c >= p).unwrap_or(255);
+                // Interpolate between the levels around the matching frequency
+                let lower_cdf = if upper > 0 { reference[upper - 1] } else { 0.0 };
+                let t = if reference[upper] > lower_cdf {
+                    ((p - lower_cdf) / (reference[upper] - lower_cdf)).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                mapped[channel] = ((upper as f64 - 1.0 + t).max(0.0) / 255.0) as f32;
+            }
+            mapped
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray_levels(levels: impl Iterator<Item = u8>, width: u32) -> image::RgbaImage {
+        let pixels: Vec<u8> = levels.flat_map(|l| [l, l, l, 255]).collect();
+        let height = pixels.len() as u32 / 4 / width;
+        image::RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+
+    fn assert_monotonic(values: &[f32]) {
+        assert!(
+            values.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{:?}",
+            values
+        );
+    }
+
+    #[test]
+    fn clip_bounds_without_clipping_are_the_occupied_extremes() {
+        let mut histogram = vec![0u64; 256];
+        histogram[12] = 3;
+        histogram[100] = 50;
+        histogram[240] = 1;
+        assert_eq!(
+            clip_bounds(&histogram, 0.0, 0.0),
+            (12.0 / 255.0, 240.0 / 255.0)
+        );
+        // Clipping more than the outermost levels hold moves past them
+        assert_eq!(
+            clip_bounds(&histogram, 10.0, 1.0),
+            (100.0 / 255.0, 240.0 / 255.0)
+        );
+    }
+
+    #[test]
+    fn clip_bounds_of_flat_images_stretch_nothing() {
+        let mut histogram = vec![0u64; 256];
+        histogram[80] = 10;
+        assert_eq!(clip_bounds(&histogram, 0.0, 0.0), (0.0, 1.0));
+        assert_eq!(clip_bounds(&[0; 256], 0.0, 0.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn equalizing_a_uniform_histogram_is_the_identity() {
+        let image = gray_levels(0..=255, 16);
+        for clip_limit in [0.0, 2.0] {
+            let rows = equalize_rows(&image, 1, clip_limit);
+            for (level, value) in rows.iter().enumerate() {
+                assert!((value - level as f32 / 255.0).abs() < 1e-5, "{}", level);
+            }
+        }
+    }
+
+    #[test]
+    fn equalized_levels_increase() {
+        // Skewed towards the shadows, with a gap
+        let levels =
+            (0..1024u32).map(|i| ((i * i / 4096) as u8).min(90) + (i % 3 == 0) as u8 * 150);
+        let image = gray_levels(levels, 32);
+        for (tiles, clip_limit) in [(1, 0.0), (2, 0.0), (2, 3.0), (4, 1.5)] {
+            let rows = equalize_rows(&image, tiles, clip_limit);
+            assert_eq!(rows.len(), (tiles * tiles * 256) as usize);
+            for row in rows.chunks(256) {
+                assert_monotonic(row);
+                assert!(row.iter().all(|value| (0.0..=1.0 + 1e-6).contains(value)));
+            }
+        }
+    }
+
+    #[test]
+    fn transparent_tiles_are_left_alone() {
+        let image = image::RgbaImage::new(8, 8);
+        let rows = equalize_rows(&image, 2, 2.0);
+        for (index, value) in rows.iter().enumerate() {
+            assert_eq!(*value, (index % 256) as f32 / 255.0);
+        }
+    }
+
+    #[test]
+    fn matching_an_image_to_itself_is_the_identity() {
+        let uniform = cdfs(&gray_levels(0..=255, 16));
+        for (level, mapped) in matched_levels(&uniform, &uniform).iter().enumerate() {
+            assert_eq!(mapped[..3], [level as f32 / 255.0; 3]);
+        }
+        // Levels missing from the image have no frequency of their own to match
+        let image = gray_levels((0..1024u32).map(|i| (i % 7 * 30 + i % 3) as u8), 32);
+        let histogram = &stats::analyze(&image).red.histogram;
+        let cdf = cdfs(&image);
+        for (level, mapped) in matched_levels(&cdf, &cdf).iter().enumerate() {
+            if histogram[level] > 0 {
+                assert_eq!(mapped[..3], [level as f32 / 255.0; 3]);
+            }
+        }
+    }
+
+    #[test]
+    fn matched_levels_increase() {
+        let dark = cdfs(&gray_levels((0..1024u32).map(|i| (i * i / 8192) as u8), 32));
+        let light = cdfs(&gray_levels((0..1024u32).map(|i| 255 - (i % 97) as u8), 32));
+        let uniform = cdfs(&gray_levels(0..=255, 16));
+        for (source, reference) in [(&dark, &light), (&light, &dark), (&dark, &uniform)] {
+            let levels = matched_levels(source, reference);
+            for channel in 0..3 {
+                let mapped: Vec<f32> = levels.iter().map(|mapped| mapped[channel]).collect();
+                assert_monotonic(&mapped);
+                assert!(mapped.iter().all(|value| (0.0..=1.0).contains(value)));
+            }
+        }
+    }
+}
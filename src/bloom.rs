@@ -0,0 +1,232 @@
+use crate::error::ShaderToyError;
+use crate::programs;
+
+/// Glow of the bright parts of a texture, computed on the GPU from a chain of
+/// half resolution blurs added back together, each level reaching twice as far.
+pub struct Bloom {
+    prefilter: three_d::Program,
+    downsample: three_d::Program,
+    upsample: three_d::Program,
+    /// Thresholded then blurred copies, each half the size of the previous one
+    down: Vec<three_d::Texture2D>,
+    /// Sum of the levels from the smallest one up to each size
+    up: Vec<three_d::Texture2D>,
+    threshold: f32,
+    knee: f32,
+}
+
+impl Bloom {
+    /// Prepare a bloom of a `width`×`height` texture reaching about `radius` pixels
+    pub fn new(
+        context: &three_d::Context,
+        width: u32,
+        height: u32,
+        threshold: f32,
+        knee: f32,
+        radius: f32,
+    ) -> Self {
+        // Keep the bright part, premultiplied, with a soft knee around the threshold
+        let prefilter = three_d::Program::from_source(
+            context,
+            include_str!("./presets/fullscreen.vert"),
+            "
+                uniform sampler2D u_texture;
+                uniform vec2 u_resolution;
+                uniform vec2 u_texel;
+                uniform float u_threshold;
+                uniform float u_knee;
+                out vec4 outColor;
+
+                vec3 bright(vec2 uv) {
+                    vec4 c = texture(u_texture, uv);
+                    vec3 rgb = c.rgb * c.a;
+                    float brightness = max(rgb.r, max(rgb.g, rgb.b));
+                    float soft = clamp(brightness - u_threshold + u_knee, 0.0, 2.0 * u_knee);
+                    soft = soft * soft / (4.0 * u_knee + 1e-5);
+                    float contribution = max(soft, brightness - u_threshold) / max(brightness, 1e-5);
+                    return rgb * contribution;
+                }
+
+                void main() {
+                    vec2 uv = gl_FragCoord.xy / u_resolution;
+                    vec3 sum = bright(uv + vec2(-0.5, -0.5) * u_texel)
+                        + bright(uv + vec2(0.5, -0.5) * u_texel)
+                        + bright(uv + vec2(-0.5, 0.5) * u_texel)
+                        + bright(uv + vec2(0.5, 0.5) * u_texel);
+                    outColor = vec4(sum * 0.25, 1.0);
+                }
+            ",
+        )
+        .unwrap();
+
+        // 4×4 box from four bilinear taps
+        let downsample = three_d::Program::from_source(
+            context,
+            include_str!("./presets/fullscreen.vert"),
+            "
+                uniform sampler2D u_texture;
+                uniform vec2 u_resolution;
+                uniform vec2 u_texel;
+                out vec4 outColor;
+
+                void main() {
+                    vec2 uv = gl_FragCoord.xy / u_resolution;
+                    vec3 sum = texture(u_texture, uv + vec2(-1.0, -1.0) * u_texel).rgb
+                        + texture(u_texture, uv + vec2(1.0, -1.0) * u_texel).rgb
+                        + texture(u_texture, uv + vec2(-1.0, 1.0) * u_texel).rgb
+                        + texture(u_texture, uv + vec2(1.0, 1.0) * u_texel).rgb;
+                    outColor = vec4(sum * 0.25, 1.0);
+                }
+            ",
+        )
+        .unwrap();
+
+        // Tent filtered smaller level added to the current one
+        let upsample = three_d::Program::from_source(
+            context,
+            include_str!("./presets/fullscreen.vert"),
+            "
+                uniform sampler2D u_texture;
+                uniform sampler2D u_smaller;
+                uniform vec2 u_resolution;
+                uniform vec2 u_texel;
+                out vec4 outColor;
+
+                void main() {
+                    vec2 uv = gl_FragCoord.xy / u_resolution;
+                    vec3 sum = texture(u_smaller, uv).rgb * 4.0;
+                    sum += texture(u_smaller, uv + vec2(-1.0, 0.0) * u_texel).rgb * 2.0;
+                    sum += texture(u_smaller, uv + vec2(1.0, 0.0) * u_texel).rgb * 2.0;
+                    sum += texture(u_smaller, uv + vec2(0.0, -1.0) * u_texel).rgb * 2.0;
+                    sum += texture(u_smaller, uv + vec2(0.0, 1.0) * u_texel).rgb * 2.0;
+                    sum += texture(u_smaller, uv + vec2(-1.0, -1.0) * u_texel).rgb;
+                    sum += texture(u_smaller, uv + vec2(1.0, -1.0) * u_texel).rgb;
+                    sum += texture(u_smaller, uv + vec2(-1.0, 1.0) * u_texel).rgb;
+                    sum += texture(u_smaller, uv + vec2(1.0, 1.0) * u_texel).rgb;
+                    outColor = vec4(texture(u_texture, uv).rgb + sum / 16.0, 1.0);
+                }
+            ",
+        )
+        .unwrap();
+
+        // Each level doubles the reach, stopping before levels get smaller than a few pixels
+        let wanted = radius.max(2.0).log2().ceil() as u32;
+        let available = (width.min(height).max(1) as f32).log2().floor() as u32;
+        let levels = wanted.min(available.saturating_sub(1)).max(1);
+        let sizes: Vec<(u32, u32)> = (1..=levels)
+            .map(|level| ((width >> level).max(1), (height >> level).max(1)))
+            .collect();
+
+        Self {
+            prefilter,
+            downsample,
+            upsample,
+            down: sizes
+                .iter()
+                .map(|&(w, h)| new_float_texture(context, w, h))
+                .collect(),
+            up: sizes
+                .iter()
+                .map(|&(w, h)| new_float_texture(context, w, h))
+                .collect(),
+            threshold,
+            knee,
+        }
+    }
+
+    /// Compute the glow of `input`, premultiplied in the `rgb` of a half resolution texture
+    pub fn compute(
+        &mut self,
+        context: &three_d::Context,
+        input: &three_d::Texture2D,
+    ) -> Result<&three_d::Texture2D, ShaderToyError> {
+        let clear_state = three_d::ClearState::color(0.0, 0.0, 0.0, 0.0);
+        let texel = |texture: &three_d::Texture2D| {
+            three_d::vec2(1.0 / texture.width() as f32, 1.0 / texture.height() as f32)
+        };
+        let viewport = |texture: &three_d::Texture2D| {
+            three_d::Viewport::new_at_origo(texture.width(), texture.height())
+        };
+
+        self.prefilter.use_uniform("u_threshold", self.threshold);
+        self.prefilter.use_uniform("u_knee", self.knee.max(0.0));
+        self.prefilter.use_uniform("u_texel", texel(input));
+        let target = &mut self.down[0];
+        let target_viewport = viewport(target);
+        target.as_color_target(None).clear(clear_state).write(|| {
+            programs::apply_shader(
+                context,
+                &self.prefilter,
+                &[],
+                &[("u_texture", input)],
+                target_viewport,
+            );
+            Ok::<(), ShaderToyError>(())
+        })?;
+
+        for level in 1..self.down.len() {
+            let (larger, smaller) = self.down.split_at_mut(level);
+            let source = &larger[level - 1];
+            let target_viewport = viewport(&smaller[0]);
+            self.downsample.use_uniform("u_texel", texel(source));
+            smaller[0]
+                .as_color_target(None)
+                .clear(clear_state)
+                .write(|| {
+                    programs::apply_shader(
+                        context,
+                        &self.downsample,
+                        &[],
+                        &[("u_texture", source)],
+                        target_viewport,
+                    );
+                    Ok::<(), ShaderToyError>(())
+                })?;
+        }
+
+        // The smallest level has nothing to add, start from the one above it
+        let last = self.down.len() - 1;
+        for level in (0..last).rev() {
+            let (larger, rest) = self.up.split_at_mut(level + 1);
+            let smaller = if level + 1 == last {
+                &self.down[last]
+            } else {
+                &rest[0]
+            };
+            let current = &self.down[level];
+            let target = &mut larger[level];
+            let target_viewport = viewport(target);
+            self.upsample.use_uniform("u_texel", texel(smaller));
+            target.as_color_target(None).clear(clear_state).write(|| {
+                programs::apply_shader(
+                    context,
+                    &self.upsample,
+                    &[],
+                    &[("u_texture", current), ("u_smaller", smaller)],
+                    target_viewport,
+                );
+                Ok::<(), ShaderToyError>(())
+            })?;
+        }
+
+        Ok(if last == 0 {
+            &self.down[0]
+        } else {
+            &self.up[0]
+        })
+    }
+}
+
+/// Create a floating point texture so glows brighter than white add up without clipping
+fn new_float_texture(context: &three_d::Context, width: u32, height: u32) -> three_d::Texture2D {
+    three_d::Texture2D::new_empty::<[f32; 4]>(
+        context,
+        width,
+        height,
+        three_d::Interpolation::Linear,
+        three_d::Interpolation::Linear,
+        None,
+        three_d::Wrapping::ClampToEdge,
+        three_d::Wrapping::ClampToEdge,
+    )
+}
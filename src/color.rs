@@ -0,0 +1,66 @@
+/// Rec. 709 weights of red, green and blue in luminance, as in `luminance.glsl`
+pub const LUMINANCE_WEIGHTS: [f64; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Weighted sum of red, green and blue with `LUMINANCE_WEIGHTS`
+pub fn luminance(rgb: [f64; 3]) -> f64 {
+    let [r, g, b] = LUMINANCE_WEIGHTS;
+    r * rgb[0] + g * rgb[1] + b * rgb[2]
+}
+
+/// Linear light value of an sRGB channel, both from 0 to 1
+pub fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// CIE L*a*b* of an sRGB color with channels from 0 to 1, with a D65 white point
+pub fn lab(rgb: [f64; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb.map(srgb_to_linear);
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = luminance([r, g, b]);
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let [fx, fy, fz] = [x, y, z].map(|t| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    });
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: [f64; 3], expected: [f64; 3]) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() < 0.05),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn lab_of_reference_colors() {
+        assert_near(lab([0.0, 0.0, 0.0]), [0.0, 0.0, 0.0]);
+        assert_near(lab([1.0, 1.0, 1.0]), [100.0, 0.0, 0.0]);
+        assert_near(lab([1.0, 0.0, 0.0]), [53.24, 80.09, 67.20]);
+        assert_near(lab([0.0, 0.0, 1.0]), [32.30, 79.19, -107.86]);
+    }
+
+    #[test]
+    fn srgb_transfer_is_continuous() {
+        let below = srgb_to_linear(0.04045);
+        let above = srgb_to_linear(0.04045 + 1e-9);
+        assert!((above - below).abs() < 1e-6);
+        assert_eq!(srgb_to_linear(1.0), 1.0);
+    }
+}
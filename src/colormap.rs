@@ -0,0 +1,249 @@
+use crate::error::ShaderToyError;
+use crate::io;
+use crate::raster;
+use crate::shape;
+
+/// Number of colors in ramp textures
+const RAMP_SIZE: u32 = 256;
+
+/// Rasterize gradient stops into a 1 pixel high ramp
+pub fn stops(stops: &[io::IoGradientStop]) -> Result<three_d::CpuTexture, ShaderToyError> {
+    let mut pixmap = tiny_skia::Pixmap::new(RAMP_SIZE, 1).unwrap();
+    // Stops at 0 and 1 land on the first and last pixel centers
+    let shader = tiny_skia::LinearGradient::new(
+        tiny_skia::Point::from_xy(0.5, 0.0),
+        tiny_skia::Point::from_xy(RAMP_SIZE as f32 - 0.5, 0.0),
+        shape::gradient_stops(stops)?,
+        tiny_skia::SpreadMode::Pad,
+        tiny_skia::Transform::identity(),
+    )
+    .ok_or_else(|| ShaderToyError::InvalidNode("invalid gradient map stops".to_string()))?;
+    pixmap.fill_rect(
+        tiny_skia::Rect::from_xywh(0.0, 0.0, RAMP_SIZE as f32, 1.0).unwrap(),
+        &tiny_skia::Paint {
+            shader,
+            ..Default::default()
+        },
+        tiny_skia::Transform::identity(),
+        None,
+    );
+    Ok(raster::pixmap_to_texture("ramp", &pixmap))
+}
+
+/// Evaluate a ready-made palette into a 1 pixel high ramp
+pub fn palette(palette: io::Palette) -> three_d::CpuTexture {
+    let data = (0..RAMP_SIZE)
+        .map(|i| palette_color(palette, i as f64 / (RAMP_SIZE - 1) as f64))
+        .collect();
+    three_d::CpuTexture {
+        name: "ramp".to_string(),
+        data: three_d::TextureData::RgbaU8(data),
+        width: RAMP_SIZE,
+        height: 1,
+        ..Default::default()
+    }
+}
+
+/// Opaque color of a ready-made palette at `t` from 0 to 1
+pub fn palette_color(palette: io::Palette, t: f64) -> [u8; 4] {
+    let t = t.clamp(0.0, 1.0);
+    let [r, g, b] = match palette {
+        io::Palette::Viridis => polynomial(&VIRIDIS, t),
+        io::Palette::Magma => polynomial(&MAGMA, t),
+        io::Palette::Inferno => polynomial(&INFERNO, t),
+        io::Palette::Turbo => polynomial(&TURBO, t),
+        io::Palette::Grayscale => [t; 3],
+    }
+    .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    [r, g, b, 255]
+}
+
+/// Sum of `coefficients[i] * t^i` for each channel
+fn polynomial(coefficients: &[[f64; 3]], t: f64) -> [f64; 3] {
+    coefficients
+        .iter()
+        .rev()
+        .fold([0.0; 3], |acc, c| [0, 1, 2].map(|i| acc[i] * t + c[i]))
+}
+
+// Polynomial fits of the matplotlib colormaps by Matt Zucker, https://www.shadertoy.com/view/WlfXRN
+const VIRIDIS: [[f64; 3]; 7] = [
+    [
+        0.277_727_327_223_417_7,
+        0.005_407_344_544_966_578,
+        0.334_099_805_335_306_1,
+    ],
+    [
+        0.105_093_043_108_577_4,
+        1.404_613_529_898_575,
+        1.384_590_162_594_685,
+    ],
+    [
+        -0.330_861_828_725_556_3,
+        0.214_847_559_468_213,
+        0.095_095_163_028_236_59,
+    ],
+    [
+        -4.634_230_498_983_486,
+        -5.799_100_973_351_585,
+        -19.332_440_956_279_87,
+    ],
+    [
+        6.228_269_936_347_081,
+        14.179_933_366_805_09,
+        56.690_552_600_681_05,
+    ],
+    [
+        4.776_384_997_670_288,
+        -13.745_145_377_746_01,
+        -65.353_032_633_372_34,
+    ],
+    [
+        -5.435_455_855_934_631,
+        4.645_852_612_178_535,
+        26.312_435_249_583_2,
+    ],
+];
+
+const MAGMA: [[f64; 3]; 7] = [
+    [
+        -0.002_136_485_053_939_582,
+        -0.000_749_655_052_795_221,
+        -0.005_386_127_855_323_933,
+    ],
+    [
+        0.251_660_540_737_164_2,
+        0.677_523_243_683_766_8,
+        2.494_026_599_312_351,
+    ],
+    [
+        8.353_717_279_216_625,
+        -3.577_719_514_958_484,
+        0.314_467_903_013_257_3,
+    ],
+    [
+        -27.668_733_085_768_66,
+        14.264_730_780_965_33,
+        -13.649_213_188_139_22,
+    ],
+    [
+        52.176_139_812_340_68,
+        -27.943_606_071_683_51,
+        12.944_169_442_383_94,
+    ],
+    [
+        -50.768_525_364_735_88,
+        29.046_582_821_272_91,
+        4.234_152_993_845_98,
+    ],
+    [
+        18.655_705_065_918_83,
+        -11.489_773_519_977_11,
+        -5.601_961_508_734_096,
+    ],
+];
+
+const INFERNO: [[f64; 3]; 7] = [
+    [
+        0.000_218_940_369_119_226_5,
+        0.001_651_004_631_001_012,
+        -0.019_480_898_437_091_84,
+    ],
+    [
+        0.106_513_419_485_611_6,
+        0.563_956_436_788_409_1,
+        3.932_712_388_889_277,
+    ],
+    [
+        11.602_493_082_471_87,
+        -3.972_853_965_665_698,
+        -15.942_394_106_291_4,
+    ],
+    [
+        -41.703_996_131_394_59,
+        17.436_398_882_053_13,
+        44.354_145_198_728_13,
+    ],
+    [
+        77.162_935_699_427,
+        -33.402_358_942_100_92,
+        -81.807_309_257_389_93,
+    ],
+    [
+        -71.319_428_244_992_14,
+        32.626_064_263_977_23,
+        73.209_519_858_032_02,
+    ],
+    [
+        25.131_126_224_773_41,
+        -12.242_668_952_385_67,
+        -23.070_325_002_871_72,
+    ],
+];
+
+// Polynomial fit of Turbo by Ruofei Du, from the Google AI blog post introducing it
+const TURBO: [[f64; 3]; 6] = [
+    [0.135_721_38, 0.091_402_61, 0.106_673_30],
+    [4.615_392_60, 2.194_188_39, 12.641_946_08],
+    [-42.660_322_58, 4.842_966_58, -60.582_048_36],
+    [132.131_082_34, -14.185_033_33, 110.362_767_71],
+    [-152.942_393_96, 4.277_298_57, -89.903_109_12],
+    [59.286_379_43, 2.829_566_04, 27.348_249_73],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ends(texture: &three_d::CpuTexture) -> ([u8; 4], [u8; 4]) {
+        let three_d::TextureData::RgbaU8(data) = &texture.data else {
+            panic!("ramps are 8 bit RGBA");
+        };
+        (data[0], data[data.len() - 1])
+    }
+
+    #[test]
+    fn palette_ends_are_its_first_and_last_colors() {
+        for palette in [
+            io::Palette::Viridis,
+            io::Palette::Magma,
+            io::Palette::Inferno,
+            io::Palette::Turbo,
+            io::Palette::Grayscale,
+        ] {
+            assert_eq!(
+                ends(&super::palette(palette)),
+                (palette_color(palette, 0.0), palette_color(palette, 1.0))
+            );
+        }
+        assert_eq!(
+            ends(&super::palette(io::Palette::Grayscale)),
+            ([0, 0, 0, 255], [255, 255, 255, 255])
+        );
+        // Matplotlib's #440154 and #fde725, up to the polynomial fit
+        let (first, last) = ends(&super::palette(io::Palette::Viridis));
+        for (actual, expected) in first
+            .iter()
+            .chain(&last)
+            .zip([68, 1, 84, 255, 253, 231, 37, 255])
+        {
+            assert!(actual.abs_diff(expected) <= 5, "{} != {}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn stop_ends_are_the_first_and_last_stops() {
+        let stops: Vec<io::IoGradientStop> = serde_json::from_str(
+            r#"[
+                {"offset": 0, "color": [1, 0, 0, 1]},
+                {"offset": 0.5, "color": [0, 1, 0, 1]},
+                {"offset": 1, "color": [0, 0, 1, 1]}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ends(&super::stops(&stops).unwrap()),
+            ([255, 0, 0, 255], [0, 0, 255, 255])
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use serde::Serialize;
+
+use crate::color;
+use crate::colormap;
+use crate::error::ShaderToyError;
+use crate::io;
+
+/// How much two images differ. Colors are compared premultiplied, so the color of fully
+/// transparent pixels, which drivers don't agree on, doesn't count.
+#[derive(Clone, Debug, Serialize)]
+pub struct Comparison {
+    pub width: u32,
+    pub height: u32,
+    /// Largest channel difference, from 0 to 255, ignoring differences up to the tolerance
+    pub max_error: f64,
+    /// Average channel difference, from 0 to 255, ignoring differences up to the tolerance
+    pub mean_error: f64,
+    /// Pixels with a channel differing by more than the tolerance
+    pub differing_pixels: u64,
+    /// Peak signal to noise ratio in decibels, capped at `MAX_PSNR` which identical images
+    /// reach, so it stays a number in JSON
+    pub psnr: f64,
+    /// Mean structural similarity of the luminance, 1 for identical images
+    pub ssim: f64,
+    /// Average CIEDE2000 color difference, under 1 being imperceptible
+    pub mean_delta_e: f64,
+    pub max_delta_e: f64,
+}
+
+/// PSNR of identical images, whose ratio is otherwise infinite
+pub const MAX_PSNR: f64 = 100.0;
+
+/// Limits above which a comparison fails, unset ones are not checked
+#[derive(Clone, Debug, Default)]
+pub struct Thresholds {
+    pub max_error: Option<f64>,
+    pub mean_error: Option<f64>,
+    pub min_psnr: Option<f64>,
+    pub min_ssim: Option<f64>,
+    pub delta_e: Option<f64>,
+}
+
+impl Comparison {
+    /// Describe each threshold the comparison is past, empty when it passes
+    pub fn failures(&self, thresholds: &Thresholds) -> Vec<String> {
+        let mut failures = vec![];
+        if let Some(limit) = thresholds.max_error.filter(|limit| self.max_error > *limit) {
+            failures.push(format!("max error {:.2} > {}", self.max_error, limit));
+        }
+        if let Some(limit) = thresholds
+            .mean_error
+            .filter(|limit| self.mean_error > *limit)
+        {
+            failures.push(format!("mean error {:.4} > {}", self.mean_error, limit));
+        }
+        if let Some(limit) = thresholds.min_psnr.filter(|limit| self.psnr < *limit) {
+            failures.push(format!("PSNR {:.2} dB < {}", self.psnr, limit));
+        }
+        if let Some(limit) = thresholds.min_ssim.filter(|limit| self.ssim < *limit) {
+            failures.push(format!("SSIM {:.5} < {}", self.ssim, limit));
+        }
+        if let Some(limit) = thresholds
+            .delta_e
+            .filter(|limit| self.mean_delta_e > *limit)
+        {
+            failures.push(format!("mean ΔE {:.3} > {}", self.mean_delta_e, limit));
+        }
+        failures
+    }
+}
+
+/// Load an image file as 8 bit RGBA
+pub fn load(path: &std::path::Path) -> Result<image::RgbaImage, ShaderToyError> {
+    Ok(image::open(path)?.into_rgba8())
+}
+
+/// Compare two images of the same size. Channel differences up to `tolerance`, from 0 to 255,
+/// are treated as equal, absorbing rounding differences between GPU drivers.
+pub fn compare(
+    a: &image::RgbaImage,
+    b: &image::RgbaImage,
+    tolerance: u8,
+) -> Result<Comparison, ShaderToyError> {
+    check_sizes(a, b)?;
+    let (width, height) = a.dimensions();
+    let count = (width as u64 * height as u64).max(1) as f64;
+
+    let mut max_error = 0.0f64;
+    let mut error_sum = 0.0;
+    let mut squared_sum = 0.0;
+    let mut differing_pixels = 0;
+    let mut delta_e_sum = 0.0;
+    let mut max_delta_e = 0.0f64;
+    for (pa, pb) in a.pixels().zip(b.pixels()) {
+        let (ca, cb) = (premultiply(pa.0), premultiply(pb.0));
+        let mut differs = false;
+        for (x, y) in ca.iter().zip(cb.iter()) {
+            let error = (x - y).abs();
+            squared_sum += error * error;
+            let error = if error <= tolerance as f64 {
+                0.0
+            } else {
+                error
+            };
+            differs |= error > 0.0;
+            max_error = max_error.max(error);
+            error_sum += error;
+        }
+        differing_pixels += differs as u64;
+
+        let delta_e = delta_e_2000(lab(ca), lab(cb));
+        delta_e_sum += delta_e;
+        max_delta_e = max_delta_e.max(delta_e);
+    }
+
+    let mse = squared_sum / (count * 4.0);
+    Ok(Comparison {
+        width,
+        height,
+        max_error,
+        mean_error: error_sum / (count * 4.0),
+        differing_pixels,
+        psnr: if mse > 0.0 {
+            f64::min(10.0 * (255.0 * 255.0 / mse).log10(), MAX_PSNR)
+        } else {
+            MAX_PSNR
+        },
+        ssim: ssim(a, b),
+        mean_delta_e: delta_e_sum / count,
+        max_delta_e,
+    })
+}
+
+/// Heatmap of the largest channel difference of each pixel, dark blue where images match up
+/// to the tolerance and dark red for the largest difference in the images
+pub fn heatmap(
+    a: &image::RgbaImage,
+    b: &image::RgbaImage,
+    tolerance: u8,
+) -> Result<image::RgbaImage, ShaderToyError> {
+    check_sizes(a, b)?;
+    let errors: Vec<f64> = a
+        .pixels()
+        .zip(b.pixels())
+        .map(|(pa, pb)| {
+            let (ca, cb) = (premultiply(pa.0), premultiply(pb.0));
+            let error = (0..4).fold(0.0f64, |e, i| e.max((ca[i] - cb[i]).abs()));
+            if error <= tolerance as f64 {
+                0.0
+            } else {
+                error
+            }
+        })
+        .collect();
+    let max_error = errors.iter().cloned().fold(0.0, f64::max).max(1.0);
+    let pixels = errors
+        .iter()
+        .flat_map(|error| colormap::palette_color(io::Palette::Turbo, error / max_error))
+        .collect();
+    Ok(image::RgbaImage::from_raw(a.width(), a.height(), pixels).unwrap())
+}
+
+fn check_sizes(a: &image::RgbaImage, b: &image::RgbaImage) -> Result<(), ShaderToyError> {
+    if a.dimensions() != b.dimensions() {
+        return Err(ShaderToyError::Compare(format!(
+            "image sizes differ: {}x{} and {}x{}",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        )));
+    }
+    Ok(())
+}
+
+/// Color over black, from 0 to 255
+fn premultiply(pixel: [u8; 4]) -> [f64; 4] {
+    let alpha = pixel[3] as f64 / 255.0;
+    [
+        pixel[0] as f64 * alpha,
+        pixel[1] as f64 * alpha,
+        pixel[2] as f64 * alpha,
+        pixel[3] as f64,
+    ]
+}
+
+/// CIE L*a*b* of a premultiplied color
+fn lab(color: [f64; 4]) -> [f64; 3] {
+    color::lab([color[0], color[1], color[2]].map(|c| c / 255.0))
+}
+
+/// CIEDE2000 color difference, after Sharma et al. "The CIEDE2000 Color-Difference Formula:
+/// Implementation Notes, Supplementary Test Data, and Mathematical Observations"
+fn delta_e_2000(lab1: [f64; 3], lab2: [f64; 3]) -> f64 {
+    let [l1, a1, b1] = lab1;
+    let [l2, a2, b2] = lab2;
+    let pow7 = |x: f64| x.powi(7);
+    let c_bar = (a1.hypot(b1) + a2.hypot(b2)) * 0.5;
+    let g = 0.5 * (1.0 - (pow7(c_bar) / (pow7(c_bar) + pow7(25.0))).sqrt());
+    let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+    let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+    let hue = |b: f64, a: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let (h1, h2) = (hue(b1, a1), hue(b2, a2));
+
+    let delta_l = l2 - l1;
+    let delta_c = c2 - c1;
+    let delta_h = if c1 * c2 == 0.0 {
+        0.0
+    } else if h2 - h1 > 180.0 {
+        h2 - h1 - 360.0
+    } else if h2 - h1 < -180.0 {
+        h2 - h1 + 360.0
+    } else {
+        h2 - h1
+    };
+    let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h * 0.5).to_radians().sin();
+
+    let l_bar = (l1 + l2) * 0.5;
+    let c_bar = (c1 + c2) * 0.5;
+    let h_bar = if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) * 0.5
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) * 0.5
+    } else {
+        (h1 + h2 - 360.0) * 0.5
+    };
+    let cos = |degrees: f64| degrees.to_radians().cos();
+    let t =
+        1.0 - 0.17 * cos(h_bar - 30.0) + 0.24 * cos(2.0 * h_bar) + 0.32 * cos(3.0 * h_bar + 6.0)
+            - 0.20 * cos(4.0 * h_bar - 63.0);
+    let delta_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (pow7(c_bar) / (pow7(c_bar) + pow7(25.0))).sqrt();
+    let s_l = 1.0 + 0.015 * (l_bar - 50.0).powi(2) / (20.0 + (l_bar - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * c_bar;
+    let s_h = 1.0 + 0.015 * c_bar * t;
+    let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+    let (l, c, h) = (delta_l / s_l, delta_c / s_c, delta_h / s_h);
+    (l * l + c * c + h * h + r_t * c * h).sqrt()
+}
+
+/// Mean SSIM of the luminance over 11×11 Gaussian windows, after Wang et al.
+/// "Image Quality Assessment: From Error Visibility to Structural Similarity"
+fn ssim(a: &image::RgbaImage, b: &image::RgbaImage) -> f64 {
+    let (width, height) = (a.width() as usize, a.height() as usize);
+    if width == 0 || height == 0 {
+        return 1.0;
+    }
+    let luminance = |image: &image::RgbaImage| -> Vec<f64> {
+        image
+            .pixels()
+            .map(|p| {
+                let c = premultiply(p.0);
+                color::luminance([c[0], c[1], c[2]])
+            })
+            .collect()
+    };
+    let (x, y) = (luminance(a), luminance(b));
+    let product =
+        |p: &[f64], q: &[f64]| -> Vec<f64> { p.iter().zip(q).map(|(p, q)| p * q).collect() };
+
+    let blur = |values: &[f64]| gaussian_blur(values, width, height);
+    let (mu_x, mu_y) = (blur(&x), blur(&y));
+    let (xx, yy, xy) = (
+        blur(&product(&x, &x)),
+        blur(&product(&y, &y)),
+        blur(&product(&x, &y)),
+    );
+
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let sum: f64 = (0..width * height)
+        .map(|i| {
+            let (mx, my) = (mu_x[i], mu_y[i]);
+            let variance_x = xx[i] - mx * mx;
+            let variance_y = yy[i] - my * my;
+            let covariance = xy[i] - mx * my;
+            ((2.0 * mx * my + C1) * (2.0 * covariance + C2))
+                / ((mx * mx + my * my + C1) * (variance_x + variance_y + C2))
+        })
+        .sum();
+    sum / (width * height) as f64
+}
+
+/// Separable Gaussian blur with a sigma of 1.5 over 11 taps, clamped at the edges
+fn gaussian_blur(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    const RADIUS: isize = 5;
+    let weights: Vec<f64> = (-RADIUS..=RADIUS)
+        .map(|i| (-(i * i) as f64 / (2.0 * 1.5 * 1.5)).exp())
+        .collect();
+    let total: f64 = weights.iter().sum();
+
+    let pass = |input: &[f64], horizontal: bool| -> Vec<f64> {
+        (0..width * height)
+            .map(|index| {
+                let (x, y) = ((index % width) as isize, (index / width) as isize);
+                (-RADIUS..=RADIUS)
+                    .zip(&weights)
+                    .map(|(offset, weight)| {
+                        let (sx, sy) = if horizontal {
+                            ((x + offset).clamp(0, width as isize - 1), y)
+                        } else {
+                            (x, (y + offset).clamp(0, height as isize - 1))
+                        };
+                        input[sy as usize * width + sx as usize] * weight
+                    })
+                    .sum::<f64>()
+                    / total
+            })
+            .collect()
+    };
+    pass(&pass(values, true), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, pixel: [u8; 4]) -> image::RgbaImage {
+        image::RgbaImage::from_pixel(width, height, image::Rgba(pixel))
+    }
+
+    #[test]
+    fn delta_e_2000_matches_sharma_test_data() {
+        let pairs = [
+            ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+            ([50.0, 3.1571, -77.2803], [50.0, 0.0, -82.7485], 2.8615),
+            ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+            ([50.0, 2.49, -0.001], [50.0, -2.49, 0.0009], 7.1792),
+            ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+            (
+                [60.2574, -34.0099, 36.2677],
+                [60.4626, -34.1751, 39.4387],
+                1.2644,
+            ),
+            ([2.0776, 0.0795, -1.135], [0.9033, -0.0636, -0.5514], 0.9082),
+        ];
+        for (lab1, lab2, expected) in pairs {
+            let delta_e = delta_e_2000(lab1, lab2);
+            assert!(
+                (delta_e - expected).abs() < 1e-4,
+                "{:?} {:?}: {} != {}",
+                lab1,
+                lab2,
+                delta_e,
+                expected
+            );
+            assert!((delta_e_2000(lab2, lab1) - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let mut a = image(16, 16, [200, 40, 90, 255]);
+        a.put_pixel(3, 5, image::Rgba([10, 250, 0, 128]));
+        let comparison = compare(&a, &a.clone(), 0).unwrap();
+        assert_eq!(comparison.max_error, 0.0);
+        assert_eq!(comparison.differing_pixels, 0);
+        assert_eq!(comparison.psnr, MAX_PSNR);
+        assert!((comparison.ssim - 1.0).abs() < 1e-9);
+        assert_eq!(comparison.max_delta_e, 0.0);
+        assert!(serde_json::to_value(&comparison).unwrap()["psnr"].is_number());
+    }
+
+    #[test]
+    fn differences_up_to_tolerance_count_as_equal() {
+        let a = image(4, 4, [100, 100, 100, 255]);
+        let mut b = a.clone();
+        b.put_pixel(1, 2, image::Rgba([102, 100, 100, 255]));
+
+        let within = compare(&a, &b, 2).unwrap();
+        assert_eq!(within.max_error, 0.0);
+        assert_eq!(within.differing_pixels, 0);
+
+        let past = compare(&a, &b, 1).unwrap();
+        assert_eq!(past.max_error, 2.0);
+        assert_eq!(past.differing_pixels, 1);
+        assert!(past.psnr < MAX_PSNR);
+    }
+
+    #[test]
+    fn transparent_colors_are_ignored() {
+        let a = image(4, 4, [255, 0, 0, 0]);
+        let b = image(4, 4, [0, 0, 255, 0]);
+        assert_eq!(compare(&a, &b, 0).unwrap().differing_pixels, 0);
+    }
+
+    #[test]
+    fn sizes_must_match() {
+        let a = image(4, 4, [0, 0, 0, 255]);
+        let b = image(4, 5, [0, 0, 0, 255]);
+        assert!(matches!(
+            compare(&a, &b, 0),
+            Err(ShaderToyError::Compare(_))
+        ));
+        assert!(heatmap(&a, &b, 0).is_err());
+    }
+}
@@ -1,10 +1,20 @@
 use three_d::SquareMatrix;
 
+use crate::adjust;
+use crate::bloom;
+use crate::color;
+use crate::colormap;
+use crate::distance;
 use crate::error::ShaderToyError;
 use crate::io;
+use crate::layer_style;
 use crate::programs;
+use crate::shape;
+use crate::svg;
 use crate::target;
+use crate::text;
 use crate::value;
+use crate::warp;
 
 /// Represents different types of nodes that can be applied to an image
 pub enum Node {
@@ -12,17 +22,72 @@ pub enum Node {
     Composition {
         composition: Composition,
         matrix: three_d::Mat3,
+        /// Drawn instead of a quad when mesh warped
+        mesh: Option<programs::Mesh>,
+        style: Option<Box<layer_style::LayerStyle>>,
     },
-    /// An image node, containing a texture reference
+    /// An image node, containing a texture reference and the part of it drawn
     Image {
         texture: three_d::Texture2DRef,
+        region: programs::TextureRegion,
         matrix: three_d::Mat3,
+        /// Drawn instead of the region when mesh warped
+        mesh: Option<programs::Mesh>,
+        style: Option<Box<layer_style::LayerStyle>>,
     },
     /// A shader node, containing a program
     Shader {
         program: three_d::Program,
         uniforms: Vec<(String, value::UniformValue)>,
     },
+    /// A shader node which also reads images or sub-compositions, bound by name like `u_source`
+    SourceShader {
+        sources: Vec<(String, Source)>,
+        program: three_d::Program,
+        uniforms: Vec<(String, value::UniformValue)>,
+    },
+    /// A shader node which also reads the signed distance field of the input's alpha as `u_distance`
+    DistanceShader {
+        program: three_d::Program,
+        uniforms: Vec<(String, value::UniformValue)>,
+        distance: Box<distance::DistanceField>,
+        max_distance: f32,
+    },
+    /// Comparison of two inputs, the pipeline so far standing in for missing ones
+    Difference {
+        a: Option<Source>,
+        b: Option<Source>,
+        uniforms: Vec<(String, value::UniformValue)>,
+    },
+    /// A shader node whose uniforms are measured on the input read back from the GPU, plus a
+    /// lookup table bound as `u_lut` for some adjustments
+    AdjustShader {
+        program: three_d::Program,
+        uniforms: Vec<(String, value::UniformValue)>,
+        adjustment: adjust::Adjustment,
+    },
+    /// A shader node which also reads the glow of the input's bright parts as `u_bloom`
+    BloomShader {
+        program: three_d::Program,
+        uniforms: Vec<(String, value::UniformValue)>,
+        bloom: Box<bloom::Bloom>,
+    },
+}
+
+/// Extra input of a `SourceShader` node
+pub enum Source {
+    Image(three_d::Texture2DRef),
+    Composition(Box<Composition>),
+}
+
+impl Source {
+    /// Texture of the source, up to date once sub-compositions are rendered
+    fn texture(&self) -> &three_d::Texture2D {
+        match self {
+            Source::Image(texture) => texture,
+            Source::Composition(composition) => &composition.output,
+        }
+    }
 }
 
 // Composition
@@ -39,6 +104,10 @@ pub struct Composition {
     height: u32,
     /// List of nodes to be applied
     nodes: Vec<Node>,
+    /// Name of each node, to capture the result up to it
+    names: Vec<Option<String>>,
+    /// Number of renders so far, for nodes which change over time as `u_frame`
+    frame: u32,
 }
 
 impl Composition {
@@ -50,39 +119,165 @@ impl Composition {
         // Load resources and create nodes
 
         let mut nodes = vec![];
-        for node in composition.nodes.iter() {
+        let mut names = vec![];
+        for named in composition.nodes.iter() {
+            let node = &named.node;
+            names.push(named.name.clone());
             match node {
                 io::IoNode::Image(io_image) => {
-                    let path = io::resolve_resource_path(parent_dir, &io_image.path);
-                    let mut loaded = three_d_asset::io::load_async(&[path]).await.unwrap();
-                    let image = three_d::Texture2D::new(context, &loaded.deserialize("").unwrap());
-                    let matrix = transform_to_matrix(
+                    let canvas = [composition.width as f32, composition.height as f32];
+                    let (mut texture, resolution) =
+                        load_image(io_image, Some(canvas), parent_dir).await?;
+                    let mut region = image_region(io_image, &texture, resolution, composition)?;
+                    region.sampling = programs::Sampling {
+                        resampling: set_filter(&mut texture, io_image, &region),
+                        wrap: set_wrap(&mut texture, io_image),
+                    };
+                    let image = three_d::Texture2D::new(context, &texture);
+                    let (matrix, mesh) = place_layer(
                         &io_image.transform,
-                        composition.width as f32,
-                        composition.height as f32,
+                        io_image.warp.as_ref(),
+                        [
+                            region.offset[0],
+                            region.offset[1],
+                            region.size[0],
+                            region.size[1],
+                        ],
+                        region.uv(&image),
+                        composition,
+                    )?;
+
+                    nodes.push(Node::Image {
+                        texture: three_d::Texture2DRef::from_texture(image),
+                        region,
+                        matrix,
+                        mesh,
+                        style: load_layer_style(context, &io_image.effects, composition)?,
+                    });
+                }
+                io::IoNode::Text(io_text) => {
+                    let path = io::resolve_resource_path(parent_dir, &io_text.font);
+                    let font = std::fs::read(path)?;
+                    let image = three_d::Texture2D::new(context, &text::rasterize(&font, io_text)?);
+                    let matrix = transform_to_matrix(&io_text.transform, texture_bounds(&image));
+
+                    nodes.push(Node::Image {
+                        region: programs::TextureRegion::full(&image),
+                        texture: three_d::Texture2DRef::from_texture(image),
+                        matrix,
+                        mesh: None,
+                        style: load_layer_style(context, &io_text.effects, composition)?,
+                    });
+                }
+                io::IoNode::Shape(io_shape) => {
+                    let image = three_d::Texture2D::new(
+                        context,
+                        &shape::rasterize(io_shape, composition.width, composition.height)?,
                     );
+                    let matrix = transform_to_matrix(&io_shape.transform, texture_bounds(&image));
 
                     nodes.push(Node::Image {
+                        region: programs::TextureRegion::full(&image),
                         texture: three_d::Texture2DRef::from_texture(image),
                         matrix,
+                        mesh: None,
+                        style: load_layer_style(context, &io_shape.effects, composition)?,
                     });
                 }
                 io::IoNode::Composition(io) => {
                     let c = Box::pin(Self::load(context, io, parent_dir)).await?;
-                    let matrix = transform_to_matrix(
+                    let (matrix, mesh) = place_layer(
                         &io.transform,
-                        composition.width as f32,
-                        composition.height as f32,
-                    );
+                        io.warp.as_ref(),
+                        [0.0, 0.0, io.width as f32, io.height as f32],
+                        [0.0, 1.0, 1.0, 0.0],
+                        composition,
+                    )?;
 
                     nodes.push(Node::Composition {
                         composition: c,
                         matrix,
+                        mesh,
+                        style: load_layer_style(context, &io.effects, composition)?,
                     });
                 }
+                io::IoNode::Tile(..)
+                | io::IoNode::Displace { .. }
+                | io::IoNode::SetAlpha {
+                    source: io::AlphaSource::Input { .. },
+                }
+                | io::IoNode::GradientMap { .. }
+                | io::IoNode::LensBlur { .. } => {
+                    nodes.push(Box::pin(load_source_node(context, node, parent_dir)).await?);
+                }
+                io::IoNode::Difference {
+                    a,
+                    b,
+                    mode,
+                    amplify,
+                    threshold,
+                } => {
+                    nodes.push(Node::Difference {
+                        a: Box::pin(load_optional_source(context, a, parent_dir)).await?,
+                        b: Box::pin(load_optional_source(context, b, parent_dir)).await?,
+                        uniforms: vec![
+                            (
+                                "u_mode".to_string(),
+                                match mode {
+                                    io::DifferenceMode::Absolute => 0.0,
+                                    io::DifferenceMode::Signed => 1.0,
+                                    io::DifferenceMode::Mask => 2.0,
+                                }
+                                .into(),
+                            ),
+                            ("u_amplify".to_string(), (*amplify).into()),
+                            ("u_threshold".to_string(), (*threshold).into()),
+                        ],
+                    });
+                }
+                io::IoNode::Bloom {
+                    threshold,
+                    knee,
+                    radius,
+                    intensity,
+                } => {
+                    let bloom = bloom::Bloom::new(
+                        context,
+                        composition.width,
+                        composition.height,
+                        *threshold,
+                        *knee,
+                        *radius,
+                    );
+                    nodes.push(Node::BloomShader {
+                        program: three_d::Program::from_source(
+                            context,
+                            include_str!("./presets/bloom.vert"),
+                            include_str!("./presets/bloom.frag"),
+                        )
+                        .unwrap(),
+                        uniforms: vec![("u_intensity".to_string(), (*intensity).into())],
+                        bloom: Box::new(bloom),
+                    });
+                }
+                io::IoNode::AutoLevels { .. }
+                | io::IoNode::AutoContrast { .. }
+                | io::IoNode::AutoWhiteBalance { .. }
+                | io::IoNode::Equalize { .. }
+                | io::IoNode::MatchColor { .. } => {
+                    nodes.push(load_adjust_node(context, node, parent_dir)?);
+                }
+                io::IoNode::Stroke { .. } => {
+                    nodes.push(load_distance_node(
+                        context,
+                        node,
+                        composition.width,
+                        composition.height,
+                    ));
+                }
                 _ => {
                     // Load shader node
-                    nodes.push(load_shader_node(context, node, parent_dir));
+                    nodes.push(load_shader_node(context, node, parent_dir, composition)?);
                 }
             }
         }
@@ -94,6 +289,8 @@ impl Composition {
             width: composition.width,
             height: composition.height,
             nodes,
+            names,
+            frame: 0,
         })
     }
 
@@ -106,7 +303,7 @@ impl Composition {
     ) -> Result<(), ShaderToyError> {
         let clear_state = three_d::ClearState::default();
 
-        self.apply_nodes(context, programs)?;
+        self.apply_nodes(context, programs, None)?;
 
         // Copy final output to the target
         target.clear(context, clear_state);
@@ -123,30 +320,109 @@ impl Composition {
         Ok(())
     }
 
+    /// Render the image up to and including the node called `name`, in this composition or a
+    /// nested one. Returns `None` when there is no such node.
+    pub fn render_node(
+        &mut self,
+        context: &three_d::Context,
+        programs: &programs::Programs,
+        name: &str,
+    ) -> Result<Option<image::RgbaImage>, ShaderToyError> {
+        self.apply_nodes(context, programs, Some(name))
+    }
+
+    /// Set a uniform on every shader declaring it, in this composition and nested ones, replacing
+    /// any value given before
+    pub fn set_uniform(&mut self, name: &str, value: &value::UniformValue) {
+        for node in self.nodes.iter_mut() {
+            let (program, uniforms) = match node {
+                Node::Shader { program, uniforms }
+                | Node::DistanceShader {
+                    program, uniforms, ..
+                }
+                | Node::BloomShader {
+                    program, uniforms, ..
+                }
+                | Node::AdjustShader {
+                    program, uniforms, ..
+                } => (program, uniforms),
+                Node::SourceShader {
+                    sources,
+                    program,
+                    uniforms,
+                } => {
+                    for (_, source) in sources.iter_mut() {
+                        if let Source::Composition(composition) = source {
+                            composition.set_uniform(name, value);
+                        }
+                    }
+                    (program, uniforms)
+                }
+                Node::Difference { a, b, .. } => {
+                    for source in [a, b].into_iter().flatten() {
+                        if let Source::Composition(composition) = source {
+                            composition.set_uniform(name, value);
+                        }
+                    }
+                    continue;
+                }
+                Node::Composition { composition, .. } => {
+                    composition.set_uniform(name, value);
+                    continue;
+                }
+                Node::Image { .. } => continue,
+            };
+            if program.requires_uniform(name) {
+                uniforms.retain(|(existing, _)| existing != name);
+                uniforms.push((name.to_string(), value.clone()));
+            }
+        }
+    }
+
+    /// Apply the nodes in order. When `capture` names a node, stop right after it and return
+    /// the result so far of the composition holding it.
     fn apply_nodes(
         &mut self,
         context: &three_d::Context,
         programs: &programs::Programs,
-    ) -> Result<(), ShaderToyError> {
+        capture: Option<&str>,
+    ) -> Result<Option<image::RgbaImage>, ShaderToyError> {
         let clear_state = three_d::ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0);
-        let u_resolution = three_d::Vector2::new(self.width as f32, self.height as f32);
+        self.frame = self.frame.wrapping_add(1);
 
-        for node in self.nodes.iter_mut() {
+        for (node, name) in self.nodes.iter_mut().zip(self.names.iter()) {
             // Apply each node
             match node {
-                Node::Image { texture, matrix } => {
+                Node::Image {
+                    texture,
+                    region,
+                    matrix,
+                    mesh,
+                    style,
+                } => {
                     self.intermediate
                         .as_color_target(None)
                         .clear(clear_state)
                         .write(|| {
-                            programs.draw_texture(
-                                context,
-                                texture,
-                                *matrix,
-                                three_d::Viewport::new_at_origo(self.width, self.height),
-                            );
+                            let viewport = three_d::Viewport::new_at_origo(self.width, self.height);
+                            match mesh {
+                                Some(mesh) => programs.draw_texture_mesh(
+                                    context,
+                                    texture,
+                                    mesh,
+                                    region.sampling,
+                                    *matrix,
+                                    viewport,
+                                ),
+                                None => programs.draw_texture_region(
+                                    context, texture, region, *matrix, viewport,
+                                ),
+                            }
                             Ok::<(), ShaderToyError>(())
                         })?;
+                    if let Some(style) = style {
+                        style.apply(context, programs, &mut self.intermediate, &self.input)?;
+                    }
                     self.output
                         .as_color_target(None)
                         .clear(clear_state)
@@ -161,55 +437,148 @@ impl Composition {
                         })?;
                 }
                 Node::Shader { program, uniforms } => {
+                    if program.requires_uniform("u_frame") {
+                        program.use_uniform("u_frame", self.frame as f32);
+                    }
                     self.output
                         .as_color_target(None)
                         .clear(clear_state)
                         .write(|| {
-                            let a_uv = three_d::VertexBuffer::new_with_data(
+                            programs::apply_shader(
                                 context,
-                                &[
-                                    three_d::vec2(0.0, 0.0),
-                                    three_d::vec2(0.0, 1.0),
-                                    three_d::vec2(1.0, 1.0),
-                                    three_d::vec2(0.0, 0.0),
-                                    three_d::vec2(1.0, 1.0),
-                                    three_d::vec2(1.0, 0.0),
-                                ],
+                                program,
+                                uniforms,
+                                &[("u_texture", &self.input)],
+                                three_d::Viewport::new_at_origo(self.width, self.height),
                             );
-                            let geom = three_d::VertexBuffer::new_with_data(
+                            Ok::<(), ShaderToyError>(())
+                        })?;
+                }
+                Node::SourceShader {
+                    sources,
+                    program,
+                    uniforms,
+                } => {
+                    for (_, source) in sources.iter_mut() {
+                        if let Source::Composition(composition) = source {
+                            if let Some(captured) =
+                                composition.apply_nodes(context, programs, capture)?
+                            {
+                                return Ok(Some(captured));
+                            }
+                        }
+                    }
+                    let mut textures = vec![("u_texture", &self.input)];
+                    textures.extend(
+                        sources
+                            .iter()
+                            .map(|(name, source)| (name.as_str(), source.texture())),
+                    );
+                    self.output
+                        .as_color_target(None)
+                        .clear(clear_state)
+                        .write(|| {
+                            programs::apply_shader(
                                 context,
-                                &[
-                                    three_d::vec3(-1.0, -1.0, 0.0),
-                                    three_d::vec3(-1.0, 1.0, 0.0),
-                                    three_d::vec3(1.0, 1.0, 0.0),
-                                    three_d::vec3(-1.0, -1.0, 0.0),
-                                    three_d::vec3(1.0, 1.0, 0.0),
-                                    three_d::vec3(1.0, -1.0, 0.0),
-                                ],
+                                program,
+                                uniforms,
+                                &textures,
+                                three_d::Viewport::new_at_origo(self.width, self.height),
                             );
-
-                            // Apply shader node
-                            if program.requires_uniform("u_resolution") {
-                                program.use_uniform("u_resolution", u_resolution);
-                            }
-                            for (key, value) in uniforms.iter() {
-                                if program.requires_uniform(key) {
-                                    value.apply(program, key);
-                                }
-                            }
-                            if program.requires_attribute("a_uv") {
-                                program.use_vertex_attribute("a_uv", &a_uv);
-                            }
-                            if program.requires_attribute("a_position") {
-                                program.use_vertex_attribute("a_position", &geom);
-                            }
-                            if program.requires_uniform("u_texture") {
-                                program.use_texture("u_texture", &self.input);
+                            Ok::<(), ShaderToyError>(())
+                        })?;
+                }
+                Node::DistanceShader {
+                    program,
+                    uniforms,
+                    distance,
+                    max_distance,
+                } => {
+                    let distance = distance.compute(context, &self.input, *max_distance)?;
+                    self.output
+                        .as_color_target(None)
+                        .clear(clear_state)
+                        .write(|| {
+                            programs::apply_shader(
+                                context,
+                                program,
+                                uniforms,
+                                &[("u_texture", &self.input), ("u_distance", distance)],
+                                three_d::Viewport::new_at_origo(self.width, self.height),
+                            );
+                            Ok::<(), ShaderToyError>(())
+                        })?;
+                }
+                Node::Difference { a, b, uniforms } => {
+                    for source in [&mut *a, &mut *b].into_iter().flatten() {
+                        if let Source::Composition(composition) = source {
+                            if let Some(captured) =
+                                composition.apply_nodes(context, programs, capture)?
+                            {
+                                return Ok(Some(captured));
                             }
-                            program.draw_arrays(
-                                three_d::RenderStates::default(),
+                        }
+                    }
+                    let a = a.as_ref().map_or(&self.input, Source::texture);
+                    let b = b.as_ref().map_or(&self.input, Source::texture);
+                    self.output
+                        .as_color_target(None)
+                        .clear(clear_state)
+                        .write(|| {
+                            programs.difference_textures(
+                                context,
+                                a,
+                                b,
+                                uniforms,
+                                three_d::Viewport::new_at_origo(self.width, self.height),
+                            );
+                            Ok::<(), ShaderToyError>(())
+                        })?;
+                }
+                Node::AdjustShader {
+                    program,
+                    uniforms,
+                    adjustment,
+                } => {
+                    // Measure the image so far on the CPU
+                    let image = read_texture(context, programs, &self.input)?;
+                    let analysis = adjustment.analyze(context, &image);
+                    let uniforms: Vec<_> =
+                        uniforms.iter().cloned().chain(analysis.uniforms).collect();
+                    let mut textures = vec![("u_texture", &self.input)];
+                    if let Some(lut) = &analysis.lut {
+                        textures.push(("u_lut", lut));
+                    }
+                    self.output
+                        .as_color_target(None)
+                        .clear(clear_state)
+                        .write(|| {
+                            programs::apply_shader(
+                                context,
+                                program,
+                                &uniforms,
+                                &textures,
+                                three_d::Viewport::new_at_origo(self.width, self.height),
+                            );
+                            Ok::<(), ShaderToyError>(())
+                        })?;
+                }
+                Node::BloomShader {
+                    program,
+                    uniforms,
+                    bloom,
+                } => {
+                    let bloom = bloom.compute(context, &self.input)?;
+                    self.output
+                        .as_color_target(None)
+                        .clear(clear_state)
+                        .write(|| {
+                            programs::apply_shader(
+                                context,
+                                program,
+                                uniforms,
+                                &[("u_texture", &self.input), ("u_bloom", bloom)],
                                 three_d::Viewport::new_at_origo(self.width, self.height),
-                                geom.vertex_count(),
                             );
                             Ok::<(), ShaderToyError>(())
                         })?;
@@ -217,21 +586,39 @@ impl Composition {
                 Node::Composition {
                     composition,
                     matrix,
+                    mesh,
+                    style,
                 } => {
-                    composition.apply_nodes(context, programs)?;
+                    if let Some(captured) = composition.apply_nodes(context, programs, capture)? {
+                        return Ok(Some(captured));
+                    }
 
                     self.intermediate
                         .as_color_target(None)
                         .clear(clear_state)
                         .write(|| {
-                            programs.draw_texture(
-                                context,
-                                &composition.output,
-                                *matrix,
-                                three_d::Viewport::new_at_origo(self.width, self.height),
-                            );
+                            let viewport = three_d::Viewport::new_at_origo(self.width, self.height);
+                            match mesh {
+                                Some(mesh) => programs.draw_texture_mesh(
+                                    context,
+                                    &composition.output,
+                                    mesh,
+                                    programs::Sampling::default(),
+                                    *matrix,
+                                    viewport,
+                                ),
+                                None => programs.draw_texture(
+                                    context,
+                                    &composition.output,
+                                    *matrix,
+                                    viewport,
+                                ),
+                            }
                             Ok::<(), ShaderToyError>(())
                         })?;
+                    if let Some(style) = style {
+                        style.apply(context, programs, &mut self.intermediate, &self.input)?;
+                    }
                     self.output
                         .as_color_target(None)
                         .clear(clear_state)
@@ -260,9 +647,66 @@ impl Composition {
                     );
                     Ok::<(), ShaderToyError>(())
                 })?;
+
+            if capture.is_some() && name.as_deref() == capture {
+                return read_texture(context, programs, &self.output).map(Some);
+            }
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    /// Render the image with all applied nodes into an RGBA image
+    pub fn render_image(
+        &mut self,
+        context: &three_d::Context,
+        programs: &programs::Programs,
+    ) -> Result<image::RgbaImage, ShaderToyError> {
+        self.apply_nodes(context, programs, None)?;
+        read_texture(context, programs, &self.output)
+    }
+
+    /// Render the image for normal vision followed by each color vision deficiency of
+    /// `io::Deficiency::ALL`, side by side
+    pub fn color_blindness_sheet(
+        &mut self,
+        context: &three_d::Context,
+        programs: &programs::Programs,
+        severity: f32,
+    ) -> Result<image::RgbaImage, ShaderToyError> {
+        self.apply_nodes(context, programs, None)?;
+        let deficiencies = io::Deficiency::ALL;
+        let mut sheet =
+            image::RgbaImage::new(self.width * (deficiencies.len() as u32 + 1), self.height);
+        image::imageops::replace(
+            &mut sheet,
+            &read_texture(context, programs, &self.output)?,
+            0,
+            0,
+        );
+
+        let mut variant = new_texture(context, self.width, self.height);
+        for (index, deficiency) in deficiencies.into_iter().enumerate() {
+            variant
+                .as_color_target(None)
+                .clear(three_d::ClearState::default())
+                .write(|| {
+                    programs.color_blindness(
+                        context,
+                        &self.output,
+                        color_blindness_matrix(deficiency, severity),
+                        three_d::Viewport::new_at_origo(self.width, self.height),
+                    );
+                    Ok::<(), ShaderToyError>(())
+                })?;
+            image::imageops::replace(
+                &mut sheet,
+                &read_texture(context, programs, &variant)?,
+                (index as i64 + 1) * self.width as i64,
+                0,
+            );
+        }
+        Ok(sheet)
     }
 
     /// Render the image with all applied nodes and save it to a file
@@ -272,33 +716,45 @@ impl Composition {
         programs: &programs::Programs,
         output_path: std::path::PathBuf,
     ) -> Result<(), ShaderToyError> {
-        // Create a new texture for rendering
-        let texture = new_texture(context, self.width, self.height);
-        let mut target = target::Target::Pixels { texture };
-
-        // Render to the target
-        self.render(context, &mut target, programs)?;
-
-        // Save the rendered image to a file
-        let pixels = target.pixels();
-        image::save_buffer_with_format(
-            output_path,
-            &pixels,
-            self.width,
-            self.height,
-            image::ColorType::Rgba8,
-            image::ImageFormat::Png,
-        )?;
-
+        self.render_image(context, programs)?
+            .save_with_format(output_path, image::ImageFormat::Png)?;
         Ok(())
     }
 }
 
+/// Read a composition texture back, rows from the top like saved images
+fn read_texture(
+    context: &three_d::Context,
+    programs: &programs::Programs,
+    texture: &three_d::Texture2D,
+) -> Result<image::RgbaImage, ShaderToyError> {
+    let (width, height) = (texture.width(), texture.height());
+    let mut target = target::Target::Pixels {
+        texture: new_texture(context, width, height),
+    };
+    target.clear(context, three_d::ClearState::default());
+    target.write(context, || {
+        programs.draw_texture(
+            context,
+            texture,
+            three_d::Mat3::identity(),
+            three_d::Viewport::new_at_origo(width, height),
+        );
+        Ok::<(), ShaderToyError>(())
+    })?;
+    Ok(image::RgbaImage::from_raw(width, height, target.pixels()).unwrap())
+}
+
 fn load_shader_node(
     context: &three_d::Context,
     item: &io::IoNode,
     parent_dir: &std::path::Path,
-) -> Node {
+    composition: &io::IoComposition,
+) -> Result<Node, ShaderToyError> {
+    let canvas_center = [
+        composition.width as f32 * 0.5,
+        composition.height as f32 * 0.5,
+    ];
     let (vert, frag, uniforms) = match item {
         io::IoNode::Shader { frag, vert } => (
             std::fs::read_to_string(io::resolve_resource_path(parent_dir, vert)).unwrap(),
@@ -307,12 +763,12 @@ fn load_shader_node(
         ),
         io::IoNode::BlackWhite => (
             include_str!("./presets/blackwhite.vert").to_string(),
-            include_str!("./presets/blackwhite.frag").to_string(),
+            luminance_frag(include_str!("./presets/blackwhite.frag")),
             vec![],
         ),
         io::IoNode::GaussianBlur { radius } => (
             include_str!("./presets/gaussian_blur.vert").to_string(),
-            include_str!("./presets/gaussian_blur.frag").to_string(),
+            gaussian_frag(include_str!("./presets/gaussian_blur.frag")),
             vec![("u_radius".to_string(), (*radius).into())],
         ),
         io::IoNode::DropShadow {
@@ -328,16 +784,968 @@ fn load_shader_node(
                 ("u_color".to_string(), (*color).into()),
             ],
         ),
-        io::IoNode::Composition(..) | io::IoNode::Image { .. } => unreachable!(),
+        io::IoNode::Sharpen { amount } => (
+            include_str!("./presets/convolution.vert").to_string(),
+            include_str!("./presets/convolution.frag").to_string(),
+            convolution_uniforms(sharpen_kernel(*amount), 0.0)?,
+        ),
+        io::IoNode::UnsharpMask {
+            amount,
+            radius,
+            threshold,
+        } => (
+            include_str!("./presets/unsharp_mask.vert").to_string(),
+            gaussian_frag(include_str!("./presets/unsharp_mask.frag")),
+            vec![
+                ("u_amount".to_string(), (*amount).into()),
+                ("u_radius".to_string(), (*radius).into()),
+                ("u_threshold".to_string(), (*threshold).into()),
+            ],
+        ),
+        io::IoNode::EdgeDetect { operator } => (
+            include_str!("./presets/edge_detect.vert").to_string(),
+            luminance_frag(include_str!("./presets/edge_detect.frag")),
+            vec![(
+                "u_weight".to_string(),
+                match operator {
+                    io::EdgeOperator::Sobel => 2.0,
+                    io::EdgeOperator::Prewitt => 1.0,
+                }
+                .into(),
+            )],
+        ),
+        io::IoNode::Emboss { angle, strength } => (
+            include_str!("./presets/convolution.vert").to_string(),
+            include_str!("./presets/convolution.frag").to_string(),
+            convolution_uniforms(emboss_kernel(*angle, *strength), 0.5)?,
+        ),
+        io::IoNode::HighPass { radius } => (
+            include_str!("./presets/high_pass.vert").to_string(),
+            gaussian_frag(include_str!("./presets/high_pass.frag")),
+            vec![("u_radius".to_string(), (*radius).into())],
+        ),
+        io::IoNode::Convolution {
+            kernel,
+            divisor,
+            bias,
+        } => {
+            // Like SVG feConvolveMatrix, the divisor defaults to the kernel sum
+            let sum: f32 = kernel.iter().sum();
+            let divisor = divisor.unwrap_or(if sum == 0.0 { 1.0 } else { sum });
+            if divisor == 0.0 {
+                return Err(ShaderToyError::InvalidNode(
+                    "convolution divisor must not be zero".to_string(),
+                ));
+            }
+            (
+                include_str!("./presets/convolution.vert").to_string(),
+                include_str!("./presets/convolution.frag").to_string(),
+                convolution_uniforms(kernel.iter().map(|k| k / divisor).collect(), *bias)?,
+            )
+        }
+        io::IoNode::Bilateral {
+            spatial_sigma,
+            range_sigma,
+        } => (
+            include_str!("./presets/bilateral.vert").to_string(),
+            include_str!("./presets/bilateral.frag").to_string(),
+            vec![
+                ("u_spatial_sigma".to_string(), (*spatial_sigma).into()),
+                ("u_range_sigma".to_string(), (*range_sigma).into()),
+            ],
+        ),
+        io::IoNode::Median { radius } => {
+            if *radius > MAX_MEDIAN_RADIUS {
+                return Err(ShaderToyError::InvalidNode(format!(
+                    "median radius must be at most {}, got {}",
+                    MAX_MEDIAN_RADIUS, radius
+                )));
+            }
+            (
+                include_str!("./presets/median.vert").to_string(),
+                include_str!("./presets/median.frag").to_string(),
+                vec![("u_radius".to_string(), (*radius as f32).into())],
+            )
+        }
+        io::IoNode::Kuwahara {
+            radius,
+            anisotropic,
+            sharpness,
+        } => (
+            include_str!("./presets/kuwahara.vert").to_string(),
+            include_str!("./presets/kuwahara.frag").to_string(),
+            vec![
+                ("u_radius".to_string(), (*radius).into()),
+                (
+                    "u_anisotropic".to_string(),
+                    if *anisotropic { 1.0 } else { 0.0 }.into(),
+                ),
+                ("u_sharpness".to_string(), (*sharpness).into()),
+            ],
+        ),
+        io::IoNode::Dilate { radius, shape } | io::IoNode::Erode { radius, shape } => (
+            include_str!("./presets/morphology.vert").to_string(),
+            include_str!("./presets/morphology.frag").to_string(),
+            vec![
+                ("u_radius".to_string(), (*radius).into()),
+                (
+                    "u_dilate".to_string(),
+                    if matches!(item, io::IoNode::Dilate { .. }) {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                    .into(),
+                ),
+                (
+                    "u_square".to_string(),
+                    match shape {
+                        io::StructuringElement::Circle => 0.0,
+                        io::StructuringElement::Square => 1.0,
+                    }
+                    .into(),
+                ),
+            ],
+        ),
+        io::IoNode::MakeSeamless { blend } => (
+            include_str!("./presets/make_seamless.vert").to_string(),
+            include_str!("./presets/make_seamless.frag").to_string(),
+            vec![("u_blend".to_string(), blend.clamp(0.0, 0.5).into())],
+        ),
+        io::IoNode::LensDistort {
+            strength,
+            zoom,
+            chromatic_aberration,
+            edge,
+        } => (
+            include_str!("./presets/lens_distort.vert").to_string(),
+            distort_frag(include_str!("./presets/lens_distort.frag")),
+            vec![
+                ("u_strength".to_string(), (*strength).into()),
+                ("u_zoom".to_string(), (*zoom).into()),
+                ("u_aberration".to_string(), (*chromatic_aberration).into()),
+                ("u_edge".to_string(), edge_mode(*edge).into()),
+            ],
+        ),
+        io::IoNode::Swirl {
+            angle,
+            radius,
+            center,
+            edge,
+        } => {
+            let [x, y] = center.unwrap_or(canvas_center);
+            (
+                include_str!("./presets/swirl.vert").to_string(),
+                distort_frag(include_str!("./presets/swirl.frag")),
+                vec![
+                    ("u_angle".to_string(), angle.to_radians().into()),
+                    ("u_radius".to_string(), radius.max(f32::EPSILON).into()),
+                    ("u_center".to_string(), (x, y).into()),
+                    ("u_edge".to_string(), edge_mode(*edge).into()),
+                ],
+            )
+        }
+        io::IoNode::Ripple {
+            amplitude,
+            wavelength,
+            phase,
+            mode,
+            center,
+            edge,
+        } => {
+            let [x, y] = center.unwrap_or(canvas_center);
+            (
+                include_str!("./presets/ripple.vert").to_string(),
+                distort_frag(include_str!("./presets/ripple.frag")),
+                vec![
+                    ("u_amplitude".to_string(), (*amplitude).into()),
+                    (
+                        "u_wavelength".to_string(),
+                        wavelength.max(f32::EPSILON).into(),
+                    ),
+                    ("u_phase".to_string(), phase.to_radians().into()),
+                    (
+                        "u_mode".to_string(),
+                        match mode {
+                            io::RippleMode::Radial => 0.0,
+                            io::RippleMode::Horizontal => 1.0,
+                            io::RippleMode::Vertical => 2.0,
+                        }
+                        .into(),
+                    ),
+                    ("u_center".to_string(), (x, y).into()),
+                    ("u_edge".to_string(), edge_mode(*edge).into()),
+                ],
+            )
+        }
+        io::IoNode::Polar { direction, edge } => (
+            include_str!("./presets/polar.vert").to_string(),
+            distort_frag(include_str!("./presets/polar.frag")),
+            vec![
+                (
+                    "u_direction".to_string(),
+                    match direction {
+                        io::PolarDirection::ToPolar => 0.0,
+                        io::PolarDirection::ToRectangular => 1.0,
+                    }
+                    .into(),
+                ),
+                ("u_edge".to_string(), edge_mode(*edge).into()),
+            ],
+        ),
+        io::IoNode::ChromaKey {
+            key_color,
+            tolerance,
+            softness,
+            spill,
+            despill,
+            matte_view,
+        } => {
+            // Spill is removed from the key's dominant channel, green for a green screen
+            let key = (0..3).fold(0, |best, i| {
+                if key_color[i] > key_color[best] {
+                    i
+                } else {
+                    best
+                }
+            });
+            (
+                include_str!("./presets/chroma_key.vert").to_string(),
+                luminance_frag(include_str!("./presets/chroma_key.frag")),
+                vec![
+                    ("u_key_color".to_string(), (*key_color).into()),
+                    ("u_tolerance".to_string(), (*tolerance).into()),
+                    ("u_softness".to_string(), (*softness).into()),
+                    ("u_spill".to_string(), spill.clamp(0.0, 1.0).into()),
+                    (
+                        "u_despill".to_string(),
+                        match despill {
+                            io::DespillMode::Average => 0.0,
+                            io::DespillMode::Max => 1.0,
+                            io::DespillMode::Min => 2.0,
+                        }
+                        .into(),
+                    ),
+                    (
+                        "u_channels".to_string(),
+                        [key, (key + 1) % 3, (key + 2) % 3].map(|i| i as f32).into(),
+                    ),
+                    (
+                        "u_matte_view".to_string(),
+                        if *matte_view { 1.0 } else { 0.0 }.into(),
+                    ),
+                ],
+            )
+        }
+        io::IoNode::LumaKey {
+            threshold,
+            softness,
+            invert,
+            matte_view,
+        } => (
+            include_str!("./presets/luma_key.vert").to_string(),
+            luminance_frag(include_str!("./presets/luma_key.frag")),
+            vec![
+                ("u_threshold".to_string(), (*threshold).into()),
+                ("u_softness".to_string(), (*softness).into()),
+                (
+                    "u_invert".to_string(),
+                    if *invert { 1.0 } else { 0.0 }.into(),
+                ),
+                (
+                    "u_matte_view".to_string(),
+                    if *matte_view { 1.0 } else { 0.0 }.into(),
+                ),
+            ],
+        ),
+        io::IoNode::Vignette {
+            amount,
+            shape,
+            size,
+            roundness,
+            feather,
+            color,
+        } => (
+            include_str!("./presets/vignette.vert").to_string(),
+            include_str!("./presets/vignette.frag").to_string(),
+            vec![
+                ("u_amount".to_string(), amount.clamp(0.0, 1.0).into()),
+                (
+                    "u_shape".to_string(),
+                    match shape {
+                        io::VignetteShape::Fit => 0.0,
+                        io::VignetteShape::Circle => 1.0,
+                    }
+                    .into(),
+                ),
+                ("u_size".to_string(), (*size).into()),
+                ("u_roundness".to_string(), (*roundness).into()),
+                ("u_feather".to_string(), (*feather).into()),
+                ("u_color".to_string(), (*color).into()),
+            ],
+        ),
+        io::IoNode::FilmGrain {
+            amount,
+            size,
+            seed,
+            animated,
+            monochrome,
+        } => (
+            include_str!("./presets/film_grain.vert").to_string(),
+            luminance_frag(include_str!("./presets/film_grain.frag")),
+            vec![
+                ("u_amount".to_string(), (*amount).into()),
+                ("u_size".to_string(), (*size).into()),
+                ("u_seed".to_string(), (*seed).into()),
+                (
+                    "u_animated".to_string(),
+                    if *animated { 1.0 } else { 0.0 }.into(),
+                ),
+                (
+                    "u_monochrome".to_string(),
+                    if *monochrome { 1.0 } else { 0.0 }.into(),
+                ),
+            ],
+        ),
+        io::IoNode::ChromaticAberration { amount } => (
+            include_str!("./presets/chromatic_aberration.vert").to_string(),
+            distort_frag(include_str!("./presets/chromatic_aberration.frag")),
+            vec![
+                ("u_amount".to_string(), (*amount).into()),
+                ("u_edge".to_string(), edge_mode(io::EdgeMode::Clamp).into()),
+            ],
+        ),
+        io::IoNode::LensFlare {
+            position,
+            intensity,
+            color,
+            ghosts,
+        } => (
+            include_str!("./presets/lens_flare.vert").to_string(),
+            include_str!("./presets/lens_flare.frag").to_string(),
+            vec![
+                ("u_position".to_string(), (position[0], position[1]).into()),
+                ("u_intensity".to_string(), (*intensity).into()),
+                ("u_color".to_string(), (*color).into()),
+                ("u_ghosts".to_string(), (*ghosts as f32).into()),
+            ],
+        ),
+        io::IoNode::ChannelMixer { matrix } => {
+            let [r, g, b, a] = matrix;
+            (
+                include_str!("./presets/channel_mixer.vert").to_string(),
+                include_str!("./presets/channel_mixer.frag").to_string(),
+                vec![
+                    (
+                        "u_rgb".to_string(),
+                        // Columns are the contributions of each input channel
+                        value::UniformValue::Mat3(three_d::Mat3::new(
+                            r[0], g[0], b[0], r[1], g[1], b[1], r[2], g[2], b[2],
+                        )),
+                    ),
+                    ("u_rgb_alpha".to_string(), [r[3], g[3], b[3]].into()),
+                    ("u_alpha".to_string(), [a[0], a[1], a[2], a[3]].into()),
+                    ("u_offset".to_string(), [r[4], g[4], b[4], a[4]].into()),
+                ],
+            )
+        }
+        io::IoNode::ColorBlindness {
+            deficiency,
+            severity,
+        } => (
+            include_str!("./presets/color_blindness.vert").to_string(),
+            srgb_frag(include_str!("./presets/color_blindness.frag")),
+            vec![(
+                "u_matrix".to_string(),
+                value::UniformValue::Mat3(color_blindness_matrix(*deficiency, *severity)),
+            )],
+        ),
+        io::IoNode::Swizzle { channels } => (
+            include_str!("./presets/swizzle.vert").to_string(),
+            include_str!("./presets/swizzle.frag").to_string(),
+            vec![("u_channels".to_string(), swizzle_channels(channels)?.into())],
+        ),
+        io::IoNode::SetAlpha { source } => (
+            include_str!("./presets/set_alpha.vert").to_string(),
+            luminance_frag(include_str!("./presets/set_alpha.frag")),
+            match source {
+                io::AlphaSource::Luminance => vec![("u_mode".to_string(), 0.0.into())],
+                io::AlphaSource::Red => vec![("u_mode".to_string(), 1.0.into())],
+                io::AlphaSource::Constant { value } => vec![
+                    ("u_mode".to_string(), 2.0.into()),
+                    ("u_value".to_string(), (*value).into()),
+                ],
+                io::AlphaSource::Input { .. } => unreachable!(),
+            },
+        ),
+        io::IoNode::Premultiply | io::IoNode::Unpremultiply => (
+            include_str!("./presets/premultiply.vert").to_string(),
+            include_str!("./presets/premultiply.frag").to_string(),
+            vec![(
+                "u_unpremultiply".to_string(),
+                if matches!(item, io::IoNode::Unpremultiply) {
+                    1.0
+                } else {
+                    0.0
+                }
+                .into(),
+            )],
+        ),
+        io::IoNode::Composition(..)
+        | io::IoNode::Image { .. }
+        | io::IoNode::Text(..)
+        | io::IoNode::Shape(..)
+        | io::IoNode::Tile(..)
+        | io::IoNode::Displace { .. }
+        | io::IoNode::Bloom { .. }
+        | io::IoNode::GradientMap { .. }
+        | io::IoNode::LensBlur { .. }
+        | io::IoNode::Difference { .. }
+        | io::IoNode::AutoLevels { .. }
+        | io::IoNode::AutoContrast { .. }
+        | io::IoNode::AutoWhiteBalance { .. }
+        | io::IoNode::Equalize { .. }
+        | io::IoNode::MatchColor { .. }
+        | io::IoNode::Stroke { .. } => unreachable!(),
+    };
+    Ok(Node::Shader {
+        program: three_d::Program::from_source(context, &vert, &frag).unwrap(),
+        uniforms,
+    })
+}
+
+fn load_layer_style(
+    context: &three_d::Context,
+    effects: &[io::IoLayerEffect],
+    composition: &io::IoComposition,
+) -> Result<Option<Box<layer_style::LayerStyle>>, ShaderToyError> {
+    if effects.is_empty() {
+        return Ok(None);
+    }
+    let style =
+        layer_style::LayerStyle::new(context, effects, composition.width, composition.height)?;
+    Ok(Some(Box::new(style)))
+}
+
+/// Load an image file, rasterizing SVGs at their drawn size, fitted into `canvas` when given.
+/// Returns the texture and its number of pixels per drawn pixel.
+async fn load_image(
+    image: &io::IoImage,
+    canvas: Option<[f32; 2]>,
+    parent_dir: &std::path::Path,
+) -> Result<(three_d::CpuTexture, [f32; 2]), ShaderToyError> {
+    let path = io::resolve_resource_path(parent_dir, &image.path);
+    let is_svg = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    if is_svg {
+        let data = std::fs::read(&path)?;
+        svg::rasterize(&data, image, canvas, parent_dir)
+    } else {
+        let mut loaded = three_d_asset::io::load_async(&[path]).await.unwrap();
+        Ok((loaded.deserialize("").unwrap(), [1.0, 1.0]))
+    }
+}
+
+/// Load an image or sub-composition read by a `SourceShader` node, with its size in pixels
+async fn load_source(
+    context: &three_d::Context,
+    node: &io::IoNode,
+    parent_dir: &std::path::Path,
+) -> Result<(Source, [f32; 2]), ShaderToyError> {
+    match node {
+        io::IoNode::Image(io_image) => {
+            // Sources are stretched over the canvas, never fitted
+            let (mut texture, resolution) = load_image(io_image, None, parent_dir).await?;
+            let size = [
+                texture.width as f32 / resolution[0],
+                texture.height as f32 / resolution[1],
+            ];
+            // Presets sample sources directly, so only the sampler's wrapping applies
+            set_wrap(&mut texture, io_image);
+            set_source_filter(&mut texture, io_image);
+            let texture = three_d::Texture2D::new(context, &texture);
+            Ok((
+                Source::Image(three_d::Texture2DRef::from_texture(texture)),
+                size,
+            ))
+        }
+        io::IoNode::Composition(io) => Ok((
+            Source::Composition(Box::new(Composition::load(context, io, parent_dir).await?)),
+            [io.width as f32, io.height as f32],
+        )),
+        _ => Err(ShaderToyError::InvalidNode(
+            "sources must be an Image or a Composition".to_string(),
+        )),
+    }
+}
+
+async fn load_optional_source(
+    context: &three_d::Context,
+    node: &Option<Box<io::IoNode>>,
+    parent_dir: &std::path::Path,
+) -> Result<Option<Source>, ShaderToyError> {
+    Ok(match node {
+        Some(node) => Some(load_source(context, node, parent_dir).await?.0),
+        None => None,
+    })
+}
+
+async fn load_source_node(
+    context: &three_d::Context,
+    node: &io::IoNode,
+    parent_dir: &std::path::Path,
+) -> Result<Node, ShaderToyError> {
+    let (sources, vert, frag, uniforms) = match node {
+        io::IoNode::Tile(tile) => {
+            let (source, size) = load_source(context, &tile.source, parent_dir).await?;
+            (
+                vec![("u_source".to_string(), source)],
+                include_str!("./presets/tile.vert").to_string(),
+                include_str!("./presets/tile.frag").to_string(),
+                tile_uniforms(tile, size)?,
+            )
+        }
+        io::IoNode::Displace {
+            source,
+            scale,
+            x_channel,
+            y_channel,
+            edge,
+        } => {
+            let (source, _) = load_source(context, source, parent_dir).await?;
+            (
+                vec![("u_source".to_string(), source)],
+                include_str!("./presets/displace.vert").to_string(),
+                distort_frag(include_str!("./presets/displace.frag")),
+                vec![
+                    ("u_scale".to_string(), (*scale).into()),
+                    (
+                        "u_channels".to_string(),
+                        (channel_index(*x_channel), channel_index(*y_channel)).into(),
+                    ),
+                    ("u_edge".to_string(), edge_mode(*edge).into()),
+                ],
+            )
+        }
+        io::IoNode::SetAlpha {
+            source: io::AlphaSource::Input { source, channel },
+        } => {
+            let (source, _) = load_source(context, source, parent_dir).await?;
+            (
+                vec![("u_source".to_string(), source)],
+                include_str!("./presets/set_alpha.vert").to_string(),
+                luminance_frag(include_str!("./presets/set_alpha.frag")),
+                vec![
+                    (
+                        "u_mode".to_string(),
+                        if channel.is_some() { 3.0 } else { 4.0 }.into(),
+                    ),
+                    (
+                        "u_channel".to_string(),
+                        channel.map_or(0.0, channel_index).into(),
+                    ),
+                ],
+            )
+        }
+        io::IoNode::GradientMap { ramp, amount } => {
+            let source = match ramp {
+                io::IoRamp::Stops { stops } => ramp_source(context, colormap::stops(stops)?),
+                io::IoRamp::Palette { palette } => {
+                    ramp_source(context, colormap::palette(*palette))
+                }
+                io::IoRamp::Image { source } => load_source(context, source, parent_dir).await?.0,
+            };
+            (
+                vec![("u_source".to_string(), source)],
+                include_str!("./presets/gradient_map.vert").to_string(),
+                luminance_frag(include_str!("./presets/gradient_map.frag")),
+                vec![("u_amount".to_string(), (*amount).into())],
+            )
+        }
+        io::IoNode::LensBlur {
+            radius,
+            aperture,
+            rotate,
+            highlight_boost,
+            highlight_threshold,
+            samples,
+            depth_map,
+            focal_distance,
+            focal_range,
+        } => {
+            let mut sources = vec![];
+            if let Some(depth_map) = depth_map {
+                let (depth, _) = load_source(context, depth_map, parent_dir).await?;
+                sources.push(("u_depth".to_string(), depth));
+            }
+            if let io::IoAperture::Image { source } = aperture {
+                let (kernel, _) = load_source(context, source, parent_dir).await?;
+                sources.push(("u_kernel".to_string(), kernel));
+            }
+            (
+                sources,
+                include_str!("./presets/lens_blur.vert").to_string(),
+                luminance_frag(include_str!("./presets/lens_blur.frag")),
+                vec![
+                    ("u_radius".to_string(), radius.max(0.0).into()),
+                    (
+                        "u_aperture".to_string(),
+                        match aperture {
+                            io::IoAperture::Circle => 0.0,
+                            io::IoAperture::Hexagon => 1.0,
+                            io::IoAperture::Image { .. } => 2.0,
+                        }
+                        .into(),
+                    ),
+                    ("u_rotation".to_string(), rotate.to_radians().into()),
+                    ("u_boost".to_string(), highlight_boost.max(0.0).into()),
+                    ("u_threshold".to_string(), (*highlight_threshold).into()),
+                    ("u_samples".to_string(), (*samples as f32).into()),
+                    (
+                        "u_has_depth".to_string(),
+                        if depth_map.is_some() { 1.0 } else { 0.0 }.into(),
+                    ),
+                    ("u_focal_distance".to_string(), (*focal_distance).into()),
+                    ("u_focal_range".to_string(), (*focal_range).into()),
+                ],
+            )
+        }
+        _ => unreachable!(),
     };
-    Node::Shader {
+
+    Ok(Node::SourceShader {
+        sources,
         program: three_d::Program::from_source(context, &vert, &frag).unwrap(),
         uniforms,
+    })
+}
+
+/// Upload a generated ramp, without mipmaps which would blend distant colors
+fn ramp_source(context: &three_d::Context, mut texture: three_d::CpuTexture) -> Source {
+    texture.mip_map_filter = None;
+    texture.wrap_s = three_d::Wrapping::ClampToEdge;
+    texture.wrap_t = three_d::Wrapping::ClampToEdge;
+    Source::Image(three_d::Texture2DRef::from_texture(
+        three_d::Texture2D::new(context, &texture),
+    ))
+}
+
+fn tile_uniforms(
+    tile: &io::IoTile,
+    size: [f32; 2],
+) -> Result<Vec<(String, value::UniformValue)>, ShaderToyError> {
+    let tile_size = [
+        size[0] * tile.scale.x().abs(),
+        size[1] * tile.scale.y().abs(),
+    ];
+    let cell = [
+        tile_size[0] + tile.spacing[0],
+        tile_size[1] + tile.spacing[1],
+    ];
+    if cell[0] <= 0.0 || cell[1] <= 0.0 {
+        return Err(ShaderToyError::InvalidNode(
+            "tiles need a positive size".to_string(),
+        ));
+    }
+    let (sin, cos) = tile.rotate.to_radians().sin_cos();
+    let uniforms = vec![
+        (
+            "u_tile_size".to_string(),
+            (tile_size[0], tile_size[1]).into(),
+        ),
+        ("u_cell".to_string(), (cell[0], cell[1]).into()),
+        (
+            "u_offset".to_string(),
+            (tile.offset[0], tile.offset[1]).into(),
+        ),
+        ("u_rotation".to_string(), (cos, sin).into()),
+        (
+            "u_pattern".to_string(),
+            match tile.pattern {
+                io::TilePattern::Grid => 0.0,
+                io::TilePattern::Brick => 1.0,
+                io::TilePattern::HalfDrop => 2.0,
+            }
+            .into(),
+        ),
+    ];
+
+    Ok(uniforms)
+}
+
+fn channel_index(channel: io::Channel) -> f32 {
+    match channel {
+        io::Channel::R => 0.0,
+        io::Channel::G => 1.0,
+        io::Channel::B => 2.0,
+        io::Channel::A => 3.0,
     }
 }
 
+/// Parse swizzle letters into the input channel indices read by `swizzle.frag`
+fn swizzle_channels(channels: &str) -> Result<[f32; 4], ShaderToyError> {
+    let invalid = || {
+        ShaderToyError::InvalidNode(format!(
+            "invalid swizzle \"{}\", expected 4 of r, g, b, a, 0 or 1",
+            channels
+        ))
+    };
+    let indices = channels
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            'r' => Ok(0.0),
+            'g' => Ok(1.0),
+            'b' => Ok(2.0),
+            'a' => Ok(3.0),
+            '0' => Ok(4.0),
+            '1' => Ok(5.0),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<f32>, _>>()?;
+    indices.try_into().map_err(|_| invalid())
+}
+
+/// Fragment shader of a distortion preset, after the helpers it shares with the others
+fn distort_frag(frag: &str) -> String {
+    format!("{}{}", include_str!("./presets/distort.glsl"), frag)
+}
+
+/// Fragment shader of a preset reading luminance, with the weights they share
+fn luminance_frag(frag: &str) -> String {
+    format!("{}{}", include_str!("./presets/luminance.glsl"), frag)
+}
+
+/// Fragment shader of a preset working in linear light, after the sRGB conversions they share
+fn srgb_frag(frag: &str) -> String {
+    format!("{}{}", include_str!("./presets/srgb.glsl"), frag)
+}
+
+/// Fragment shader of a preset built on a Gaussian blur, after the sampling loop they share
+fn gaussian_frag(frag: &str) -> String {
+    format!("{}{}", include_str!("./presets/gaussian.glsl"), frag)
+}
+
+/// Linear RGB simulation of a color vision deficiency, mixed with normal vision by `severity`.
+/// Dichromacies use the severity 1 matrices of Machado et al. "A Physiologically-based Model for
+/// Simulation of Color Vision Deficiency", achromatopsia keeps the luminance.
+fn color_blindness_matrix(deficiency: io::Deficiency, severity: f32) -> three_d::Mat3 {
+    let [r, g, b] = match deficiency {
+        io::Deficiency::Protanopia => [
+            [0.152286, 1.052583, -0.204868],
+            [0.114503, 0.786281, 0.099216],
+            [-0.003882, -0.048116, 1.051998],
+        ],
+        io::Deficiency::Deuteranopia => [
+            [0.367322, 0.860646, -0.227968],
+            [0.280085, 0.672501, 0.047413],
+            [-0.011820, 0.042940, 0.968881],
+        ],
+        io::Deficiency::Tritanopia => [
+            [1.255528, -0.076749, -0.178779],
+            [-0.078411, 0.930809, 0.147602],
+            [0.004733, 0.691367, 0.303900],
+        ],
+        io::Deficiency::Achromatopsia => [color::LUMINANCE_WEIGHTS.map(|w| w as f32); 3],
+    };
+    // Columns are the contributions of each input channel
+    let matrix = three_d::Mat3::new(r[0], g[0], b[0], r[1], g[1], b[1], r[2], g[2], b[2]);
+    let severity = severity.clamp(0.0, 1.0);
+    three_d::Mat3::identity() * (1.0 - severity) + matrix * severity
+}
+
+/// Index of an edge mode in `sample_edge()` of `distort.glsl`
+fn edge_mode(edge: io::EdgeMode) -> f32 {
+    match edge {
+        io::EdgeMode::Clamp => 0.0,
+        io::EdgeMode::Repeat => 1.0,
+        io::EdgeMode::Mirror => 2.0,
+        io::EdgeMode::Transparent => 3.0,
+    }
+}
+
+fn load_adjust_node(
+    context: &three_d::Context,
+    item: &io::IoNode,
+    parent_dir: &std::path::Path,
+) -> Result<Node, ShaderToyError> {
+    let (vert, frag, adjustment) = match item {
+        io::IoNode::AutoLevels {
+            clip_shadows,
+            clip_highlights,
+        }
+        | io::IoNode::AutoContrast {
+            clip_shadows,
+            clip_highlights,
+        } => (
+            include_str!("./presets/levels.vert"),
+            include_str!("./presets/levels.frag").to_string(),
+            adjust::Adjustment::Levels {
+                clip_shadows: *clip_shadows,
+                clip_highlights: *clip_highlights,
+                per_channel: matches!(item, io::IoNode::AutoLevels { .. }),
+            },
+        ),
+        io::IoNode::AutoWhiteBalance { amount } => (
+            include_str!("./presets/white_balance.vert"),
+            srgb_frag(include_str!("./presets/white_balance.frag")),
+            adjust::Adjustment::WhiteBalance { amount: *amount },
+        ),
+        io::IoNode::Equalize {
+            tiles,
+            clip_limit,
+            amount,
+        } => {
+            // One lookup table row per tile, within the texture sizes of any GPU
+            if !(1..=64).contains(tiles) {
+                return Err(ShaderToyError::InvalidNode(format!(
+                    "equalize needs 1 to 64 tiles per side, got {}",
+                    tiles
+                )));
+            }
+            (
+                include_str!("./presets/equalize.vert"),
+                luminance_frag(include_str!("./presets/equalize.frag")),
+                adjust::Adjustment::Equalize {
+                    tiles: *tiles,
+                    clip_limit: *clip_limit,
+                    amount: *amount,
+                },
+            )
+        }
+        io::IoNode::MatchColor {
+            reference,
+            mode,
+            strength,
+        } => {
+            let path = io::resolve_resource_path(parent_dir, reference);
+            let image = image::open(path)?.into_rgba8();
+            let (vert, frag, reference) = match mode {
+                io::MatchMode::Lab => (
+                    include_str!("./presets/match_color.vert"),
+                    luminance_frag(&srgb_frag(include_str!("./presets/match_color.frag"))),
+                    adjust::Reference::lab(&image),
+                ),
+                io::MatchMode::Histogram => (
+                    include_str!("./presets/match_histogram.vert"),
+                    include_str!("./presets/match_histogram.frag").to_string(),
+                    adjust::Reference::histogram(&image),
+                ),
+            };
+            (
+                vert,
+                frag,
+                adjust::Adjustment::MatchColor {
+                    reference,
+                    strength: *strength,
+                },
+            )
+        }
+        _ => unreachable!(),
+    };
+    Ok(Node::AdjustShader {
+        program: three_d::Program::from_source(context, vert, &frag).unwrap(),
+        // Only the ones set by `set_uniform`, measured ones are added at every render
+        uniforms: vec![],
+        adjustment,
+    })
+}
+
+fn load_distance_node(
+    context: &three_d::Context,
+    item: &io::IoNode,
+    width: u32,
+    height: u32,
+) -> Node {
+    let (vert, frag, uniforms, max_distance) = match item {
+        io::IoNode::Stroke {
+            width: stroke_width,
+            color,
+            position,
+        } => (
+            include_str!("./presets/stroke.vert"),
+            include_str!("./presets/stroke.frag"),
+            vec![
+                ("u_width".to_string(), (*stroke_width).into()),
+                ("u_color".to_string(), (*color).into()),
+                (
+                    "u_position".to_string(),
+                    match position {
+                        io::StrokePosition::Outside => 0.0,
+                        io::StrokePosition::Inside => 1.0,
+                        io::StrokePosition::Center => 2.0,
+                    }
+                    .into(),
+                ),
+            ],
+            match position {
+                io::StrokePosition::Center => stroke_width * 0.5 + 1.0,
+                _ => stroke_width + 1.0,
+            },
+        ),
+        _ => unreachable!(),
+    };
+    Node::DistanceShader {
+        program: three_d::Program::from_source(context, vert, frag).unwrap(),
+        uniforms,
+        distance: Box::new(distance::DistanceField::new(context, width, height)),
+        max_distance,
+    }
+}
+
+/// Largest radius accepted by `median.frag` (7×7)
+const MAX_MEDIAN_RADIUS: u32 = 3;
+
+/// Largest kernel accepted by `convolution.frag` (9×9)
+const MAX_KERNEL_SIZE: usize = 9;
+
+/// Build the uniforms for `convolution.frag` from a square kernel
+fn convolution_uniforms(
+    kernel: Vec<f32>,
+    bias: f32,
+) -> Result<Vec<(String, value::UniformValue)>, ShaderToyError> {
+    let size = (kernel.len() as f32).sqrt() as usize;
+    if size * size != kernel.len() || size.is_multiple_of(2) || size > MAX_KERNEL_SIZE {
+        return Err(ShaderToyError::InvalidNode(format!(
+            "convolution kernel must be an odd N×N matrix up to {0}×{0}, got {1} values",
+            MAX_KERNEL_SIZE,
+            kernel.len()
+        )));
+    }
+    Ok(vec![
+        ("u_kernel".to_string(), kernel.into()),
+        ("u_size".to_string(), (size as f32).into()),
+        ("u_bias".to_string(), bias.into()),
+    ])
+}
+
+/// 3×3 Laplacian sharpening kernel
+fn sharpen_kernel(amount: f32) -> Vec<f32> {
+    let (a, c) = (-amount, 1.0 + 4.0 * amount);
+    vec![0.0, a, 0.0, a, c, a, 0.0, a, 0.0]
+}
+
+/// 3×3 directional derivative kernel, lit from `angle` degrees (counter-clockwise from +x)
+fn emboss_kernel(angle: f32, strength: f32) -> Vec<f32> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let mut kernel = Vec::with_capacity(9);
+    for row in 0..3 {
+        // Rows are listed from the top
+        let y = 1.0 - row as f32;
+        for column in 0..3 {
+            let x = column as f32 - 1.0;
+            kernel.push(strength * (x * cos + y * sin));
+        }
+    }
+    kernel
+}
+
 /// Create a new empty texture with the specified dimensions
-fn new_texture(context: &three_d::Context, width: u32, height: u32) -> three_d::Texture2D {
+pub(crate) fn new_texture(
+    context: &three_d::Context,
+    width: u32,
+    height: u32,
+) -> three_d::Texture2D {
     three_d::Texture2D::new_empty::<[u8; 4]>(
         context,
         width,
@@ -350,16 +1758,550 @@ fn new_texture(context: &three_d::Context, width: u32, height: u32) -> three_d::
     )
 }
 
-fn transform_to_matrix(
-    tr: &io::IoTransform,
-    viewport_width: f32,
-    viewport_height: f32,
-) -> three_d::Mat3 {
-    let s = three_d::Mat3::from_nonuniform_scale(tr.scale.x(), tr.scale.y());
-    let r = three_d::Mat3::from_angle_z(three_d::degrees(-1.0 * tr.rotate));
-    let t = three_d::Mat3::from_translation(three_d::vec2(
-        tr.translate[0] / viewport_width,
-        tr.translate[1] / viewport_height,
-    ));
-    t * r * s
+/// Crop, size and place an image in the composition from its fit, alignment and slicing.
+/// `resolution` is the number of texture pixels per drawn pixel.
+fn image_region(
+    image: &io::IoImage,
+    texture: &three_d::CpuTexture,
+    resolution: [f32; 2],
+    composition: &io::IoComposition,
+) -> Result<programs::TextureRegion, ShaderToyError> {
+    let [x, y, width, height] = image.source_rect.unwrap_or([
+        0.0,
+        0.0,
+        texture.width as f32 / resolution[0],
+        texture.height as f32 / resolution[1],
+    ]);
+    if width <= 0.0 || height <= 0.0 {
+        return Err(ShaderToyError::InvalidNode(format!(
+            "empty source_rect of {}x{} in {}",
+            width, height, image.path
+        )));
+    }
+    let canvas = [composition.width as f32, composition.height as f32];
+    let size = image.fit.size([width, height], canvas);
+    let [align_x, align_y] = image.align.fraction();
+    let insets = image.slices.unwrap_or([0.0; 4]);
+    let [top, right, bottom, left] = insets;
+
+    Ok(programs::TextureRegion {
+        source: [
+            x * resolution[0],
+            y * resolution[1],
+            width * resolution[0],
+            height * resolution[1],
+        ],
+        size,
+        offset: [
+            (canvas[0] - size[0]) * (align_x - 0.5),
+            (canvas[1] - size[1]) * (align_y - 0.5),
+        ],
+        slices: [
+            top * resolution[1],
+            right * resolution[0],
+            bottom * resolution[1],
+            left * resolution[0],
+        ],
+        insets,
+        sampling: programs::Sampling::default(),
+    })
+}
+
+/// Set up the wrapping of an image texture, returning what to draw past its edges
+fn set_wrap(texture: &mut three_d::CpuTexture, image: &io::IoImage) -> programs::Wrap {
+    let (wrapping, wrap) = match image.wrap {
+        io::Wrap::Clamp => (three_d::Wrapping::ClampToEdge, programs::Wrap::Clamp),
+        io::Wrap::Repeat => (three_d::Wrapping::Repeat, programs::Wrap::Repeat),
+        io::Wrap::MirroredRepeat => (
+            three_d::Wrapping::MirroredRepeat,
+            programs::Wrap::MirroredRepeat,
+        ),
+        // OpenGL ES has no border color, the shader draws it
+        io::Wrap::ClampToBorder => (
+            three_d::Wrapping::ClampToEdge,
+            programs::Wrap::Border(image.border_color),
+        ),
+    };
+    texture.wrap_s = wrapping;
+    texture.wrap_t = wrapping;
+    wrap
+}
+
+/// Filter used by `Filter::Auto` for an image drawn `magnification` times its pixel size
+fn auto_filter(magnification: [f32; 2], on_grid: bool, warped: bool) -> io::Filter {
+    let whole = |m: f32| (m - m.round()).abs() < 1e-4;
+    if warped || magnification.iter().any(|m| *m < 1.0 - 1e-4) {
+        io::Filter::Mipmap
+    } else if on_grid && magnification.into_iter().all(whole) {
+        io::Filter::Nearest
+    } else {
+        io::Filter::Linear
+    }
+}
+
+/// Set up the sampling of an image texture for its filter, returning how to draw it
+fn set_filter(
+    texture: &mut three_d::CpuTexture,
+    image: &io::IoImage,
+    region: &programs::TextureRegion,
+) -> programs::Resampling {
+    let filter = match image.filter {
+        io::Filter::Auto => {
+            let [scale_x, scale_y] = image.transform.scale();
+            let magnification = [
+                region.size[0] * scale_x / region.source[2],
+                region.size[1] * scale_y / region.source[3],
+            ];
+            // Nine-slice middles stretch by their own factor
+            let on_grid = image.transform.axis_aligned() && region.slices == [0.0; 4];
+            auto_filter(magnification, on_grid, image.warp.is_some())
+        }
+        filter => filter,
+    };
+
+    let (interpolation, mip_map_filter, resampling) = match filter {
+        io::Filter::Nearest => (
+            three_d::Interpolation::Nearest,
+            None,
+            programs::Resampling::Sampler,
+        ),
+        io::Filter::Auto | io::Filter::Linear => (
+            three_d::Interpolation::Linear,
+            None,
+            programs::Resampling::Sampler,
+        ),
+        io::Filter::Mipmap => (
+            three_d::Interpolation::Linear,
+            Some(three_d::Interpolation::Linear),
+            programs::Resampling::Sampler,
+        ),
+        // Shader kernels fetch texels themselves, from mip levels when downscaling
+        io::Filter::Bicubic => (
+            three_d::Interpolation::Nearest,
+            Some(three_d::Interpolation::Nearest),
+            programs::Resampling::Bicubic,
+        ),
+        io::Filter::Lanczos3 => (
+            three_d::Interpolation::Nearest,
+            Some(three_d::Interpolation::Nearest),
+            programs::Resampling::Lanczos3,
+        ),
+        io::Filter::Area => (
+            three_d::Interpolation::Nearest,
+            Some(three_d::Interpolation::Nearest),
+            programs::Resampling::Area,
+        ),
+    };
+    texture.min_filter = interpolation;
+    texture.mag_filter = interpolation;
+    texture.mip_map_filter = mip_map_filter;
+    resampling
+}
+
+/// Set up the sampling of an image read by a `SourceShader` node. Presets sample it through the
+/// sampler alone, so filters run by shader kernels fall back to mipmaps.
+fn set_source_filter(texture: &mut three_d::CpuTexture, image: &io::IoImage) {
+    let (interpolation, mip_map_filter) = match image.filter {
+        io::Filter::Nearest => (three_d::Interpolation::Nearest, None),
+        io::Filter::Linear => (three_d::Interpolation::Linear, None),
+        io::Filter::Auto
+        | io::Filter::Mipmap
+        | io::Filter::Bicubic
+        | io::Filter::Lanczos3
+        | io::Filter::Area => (
+            three_d::Interpolation::Linear,
+            Some(three_d::Interpolation::Linear),
+        ),
+    };
+    texture.min_filter = interpolation;
+    texture.mag_filter = interpolation;
+    texture.mip_map_filter = mip_map_filter;
+}
+
+/// Matrix, and mesh when mesh warped, drawing a layer from its transform or warp.
+/// `bounds` and `uv` are the layer's box and texture coordinates, as taken by `warp::load`.
+fn place_layer(
+    transforms: &io::IoTransforms,
+    warp: Option<&io::IoWarp>,
+    bounds: [f32; 4],
+    uv: [f32; 4],
+    composition: &io::IoComposition,
+) -> Result<(three_d::Mat3, Option<programs::Mesh>), ShaderToyError> {
+    let Some(warp) = warp else {
+        return Ok((transform_to_matrix(transforms, bounds), None));
+    };
+    let canvas = [composition.width as f32, composition.height as f32];
+    Ok(match warp::load(warp, bounds, uv, canvas)? {
+        warp::Warp::Matrix(matrix) => (matrix, None),
+        warp::Warp::Mesh(mesh) => (three_d::Mat3::identity(), Some(mesh)),
+    })
+}
+
+/// Matrix in pixels from the canvas center, y pointing down, of a layer drawn in `bounds`:
+/// center x, center y, width and height
+fn transform_to_matrix(transforms: &io::IoTransforms, bounds: [f32; 4]) -> three_d::Mat3 {
+    transforms
+        .as_slice()
+        .iter()
+        .fold(three_d::Mat3::identity(), |matrix, transform| {
+            matrix * single_transform_to_matrix(transform, bounds)
+        })
+}
+
+/// Bounds of a texture drawn at its native size on the canvas center
+fn texture_bounds(texture: &three_d::Texture2D) -> [f32; 4] {
+    [0.0, 0.0, texture.width() as f32, texture.height() as f32]
+}
+
+fn single_transform_to_matrix(tr: &io::IoTransform, bounds: [f32; 4]) -> three_d::Mat3 {
+    let [center_x, center_y, width, height] = bounds;
+    let origin = three_d::vec2(
+        center_x - width * 0.5 + tr.origin[0].resolve(width),
+        center_y - height * 0.5 + tr.origin[1].resolve(height),
+    );
+
+    let linear = match tr.matrix {
+        // Rows as written in the project, while cgmath takes columns
+        Some(m) => three_d::Mat3::new(
+            m[0][0], m[1][0], m[2][0], m[0][1], m[1][1], m[2][1], m[0][2], m[1][2], m[2][2],
+        ),
+        None => {
+            let t = three_d::Mat3::from_translation(three_d::vec2(
+                tr.translate[0].resolve(width),
+                tr.translate[1].resolve(height),
+            ));
+            let r = three_d::Mat3::from_angle_z(three_d::degrees(tr.rotate));
+            let [skew_x, skew_y] = tr.skew.map(|a| a.to_radians().tan());
+            let k = three_d::Mat3::new(1.0, skew_y, 0.0, skew_x, 1.0, 0.0, 0.0, 0.0, 1.0);
+            let s = three_d::Mat3::from_nonuniform_scale(
+                if tr.flip_x {
+                    -tr.scale.x()
+                } else {
+                    tr.scale.x()
+                },
+                if tr.flip_y {
+                    -tr.scale.y()
+                } else {
+                    tr.scale.y()
+                },
+            );
+            t * r * k * s
+        }
+    };
+
+    three_d::Mat3::from_translation(origin) * linear * three_d::Mat3::from_translation(-origin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Layer of 100×50 pixels centered on the canvas
+    const BOUNDS: [f32; 4] = [0.0, 0.0, 100.0, 50.0];
+
+    fn matrix(json: &str) -> three_d::Mat3 {
+        transform_to_matrix(&serde_json::from_str(json).unwrap(), BOUNDS)
+    }
+
+    fn apply(matrix: three_d::Mat3, x: f32, y: f32) -> (f32, f32) {
+        let p = matrix * three_d::vec3(x, y, 1.0);
+        (p.x / p.z, p.y / p.z)
+    }
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn default_is_identity() {
+        assert_eq!(matrix("{}"), three_d::Mat3::identity());
+    }
+
+    #[test]
+    fn translate_in_pixels_and_percent() {
+        let m = matrix(r#"{"translate": [10, "50%"]}"#);
+        assert_near(apply(m, 0.0, 0.0), (10.0, 25.0));
+        let m = matrix(r#"{"translate": ["-20px", "-100%"]}"#);
+        assert_near(apply(m, 0.0, 0.0), (-20.0, -50.0));
+    }
+
+    #[test]
+    fn rotate_clockwise_around_center() {
+        let m = matrix(r#"{"rotate": 90}"#);
+        // y points down, so the right edge turns towards the bottom
+        assert_near(apply(m, 50.0, 0.0), (0.0, 50.0));
+    }
+
+    #[test]
+    fn rotate_around_origin() {
+        let m = matrix(r#"{"rotate": 90, "origin": [0, 0]}"#);
+        // The top-left corner stays in place
+        assert_near(apply(m, -50.0, -25.0), (-50.0, -25.0));
+        assert_near(apply(m, 50.0, -25.0), (-50.0, 75.0));
+        let m = matrix(r#"{"rotate": 180, "origin": ["100%", "100%"]}"#);
+        assert_near(apply(m, 50.0, 25.0), (50.0, 25.0));
+        assert_near(apply(m, -50.0, -25.0), (150.0, 75.0));
+    }
+
+    #[test]
+    fn scale_and_flip() {
+        let m = matrix(r#"{"scale": [2, 3], "flip_x": true}"#);
+        assert_near(apply(m, 10.0, 10.0), (-20.0, 30.0));
+        let m = matrix(r#"{"flip_y": true, "origin": [0, 0]}"#);
+        assert_near(apply(m, 0.0, 0.0), (0.0, -50.0));
+    }
+
+    #[test]
+    fn skew() {
+        let m = matrix(r#"{"skew": [45, 0]}"#);
+        assert_near(apply(m, 0.0, 10.0), (10.0, 10.0));
+        let m = matrix(r#"{"skew": [0, 45]}"#);
+        assert_near(apply(m, 10.0, 0.0), (10.0, 10.0));
+    }
+
+    #[test]
+    fn matrix_override() {
+        let m = matrix(r#"{"matrix": [[1, 0, 5], [0, 2, 7], [0, 0, 1]], "rotate": 90}"#);
+        assert_near(apply(m, 1.0, 1.0), (6.0, 9.0));
+        // Still applied around the origin
+        let m = matrix(r#"{"matrix": [[2, 0, 0], [0, 1, 0], [0, 0, 1]], "origin": [0, 0]}"#);
+        assert_near(apply(m, -50.0, 0.0), (-50.0, 0.0));
+        assert_near(apply(m, 0.0, 0.0), (50.0, 0.0));
+    }
+
+    #[test]
+    fn lists_compose_left_to_right() {
+        let m = matrix(r#"[{"translate": [100, 0]}, {"rotate": 90}]"#);
+        assert_near(apply(m, 10.0, 0.0), (100.0, 10.0));
+        let m = matrix(r#"[{"rotate": 90}, {"translate": [100, 0]}]"#);
+        assert_near(apply(m, 10.0, 0.0), (0.0, 110.0));
+    }
+
+    #[test]
+    fn empty_source_rect_is_rejected() {
+        let composition = io::IoComposition {
+            width: 200,
+            height: 100,
+            ..Default::default()
+        };
+        let texture = three_d::CpuTexture {
+            width: 40,
+            height: 20,
+            ..Default::default()
+        };
+        let region = |json: &str| {
+            let image: io::IoImage = serde_json::from_str(json).unwrap();
+            image_region(&image, &texture, [1.0, 1.0], &composition)
+        };
+        assert!(
+            region(r#"{"path": "a.png", "fit": "contain", "source_rect": [5, 5, 0, 10]}"#).is_err()
+        );
+        assert!(
+            region(r#"{"path": "a.png", "fit": "cover", "source_rect": [5, 5, 10, -1]}"#).is_err()
+        );
+        let fitted = region(r#"{"path": "a.png", "fit": "contain"}"#).unwrap();
+        assert_eq!(fitted.size, [200.0, 100.0]);
+    }
+
+    fn assert_kernel(actual: Vec<f32>, expected: [f32; 9]) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() < 1e-6),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn convolution_kernels_are_odd_squares_up_to_9() {
+        for size in [1, 3, 5, 7, 9] {
+            let uniforms = convolution_uniforms(vec![0.0; size * size], 0.0).unwrap();
+            assert!(matches!(
+                uniforms[1],
+                (ref name, value::UniformValue::Float(s)) if name == "u_size" && s == size as f32
+            ));
+        }
+        for len in [0, 4, 8, 16, 36, 121] {
+            assert!(
+                convolution_uniforms(vec![0.0; len], 0.0).is_err(),
+                "{}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn sharpen_keeps_brightness() {
+        for amount in [0.0, 0.5, 2.0] {
+            let sum: f32 = sharpen_kernel(amount).iter().sum();
+            assert!((sum - 1.0).abs() < 1e-6);
+        }
+        assert_kernel(
+            sharpen_kernel(1.0),
+            [0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn emboss_follows_the_light_angle() {
+        assert_kernel(
+            emboss_kernel(0.0, 2.0),
+            [-2.0, 0.0, 2.0, -2.0, 0.0, 2.0, -2.0, 0.0, 2.0],
+        );
+        // Lit from the top, rows being listed from the top
+        assert_kernel(
+            emboss_kernel(90.0, 1.0),
+            [1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0, -1.0, -1.0],
+        );
+    }
+
+    /// Rows of a color matrix, each the weights of one output channel
+    fn rows(matrix: three_d::Mat3) -> [[f32; 3]; 3] {
+        [0, 1, 2].map(|i| [matrix.x[i], matrix.y[i], matrix.z[i]])
+    }
+
+    fn assert_rows(actual: three_d::Mat3, expected: [[f32; 3]; 3]) {
+        let actual = rows(actual);
+        assert!(
+            actual
+                .iter()
+                .flatten()
+                .zip(expected.iter().flatten())
+                .all(|(a, e)| (a - e).abs() < 1e-6),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn color_blindness_at_no_severity_is_the_identity() {
+        for deficiency in io::Deficiency::ALL {
+            assert_eq!(
+                color_blindness_matrix(deficiency, 0.0),
+                three_d::Mat3::identity()
+            );
+            assert_eq!(
+                color_blindness_matrix(deficiency, -1.0),
+                three_d::Mat3::identity()
+            );
+        }
+    }
+
+    #[test]
+    fn color_blindness_at_full_severity_is_machado() {
+        // Machado, Oliveira and Fernandes 2009, severity 1.0
+        let expected = [
+            (
+                io::Deficiency::Protanopia,
+                [
+                    [0.152286, 1.052583, -0.204868],
+                    [0.114503, 0.786281, 0.099216],
+                    [-0.003882, -0.048116, 1.051998],
+                ],
+            ),
+            (
+                io::Deficiency::Deuteranopia,
+                [
+                    [0.367322, 0.860646, -0.227968],
+                    [0.280085, 0.672501, 0.047413],
+                    [-0.011820, 0.042940, 0.968881],
+                ],
+            ),
+            (
+                io::Deficiency::Tritanopia,
+                [
+                    [1.255528, -0.076749, -0.178779],
+                    [-0.078411, 0.930809, 0.147602],
+                    [0.004733, 0.691367, 0.303900],
+                ],
+            ),
+            (io::Deficiency::Achromatopsia, [[0.2126, 0.7152, 0.0722]; 3]),
+        ];
+        for (deficiency, rows) in expected {
+            assert_rows(color_blindness_matrix(deficiency, 1.0), rows);
+            assert_rows(color_blindness_matrix(deficiency, 2.0), rows);
+        }
+    }
+
+    #[test]
+    fn color_blindness_keeps_white() {
+        for deficiency in io::Deficiency::ALL {
+            for severity in [0.25, 0.5, 1.0] {
+                for row in rows(color_blindness_matrix(deficiency, severity)) {
+                    let sum: f32 = row.iter().sum();
+                    assert!((sum - 1.0).abs() < 1e-4, "{:?} {}", deficiency, sum);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn auto_filter_is_nearest_for_whole_magnifications() {
+        for magnification in [[1.0, 1.0], [2.0, 2.0], [3.0, 1.0], [4.00001, 8.0]] {
+            assert!(auto_filter(magnification, true, false) == io::Filter::Nearest);
+        }
+        // Rotated, skewed or nine-sliced pixels fall between pixels
+        assert!(auto_filter([2.0, 2.0], false, false) == io::Filter::Linear);
+    }
+
+    #[test]
+    fn auto_filter_is_linear_for_fractional_magnifications() {
+        for magnification in [[1.5, 1.5], [2.0, 2.5], [1.001, 1.0]] {
+            assert!(auto_filter(magnification, true, false) == io::Filter::Linear);
+        }
+    }
+
+    #[test]
+    fn auto_filter_is_mipmap_when_downscaled_or_warped() {
+        for magnification in [[0.5, 0.5], [2.0, 0.9], [0.25, 4.0]] {
+            assert!(auto_filter(magnification, true, false) == io::Filter::Mipmap);
+        }
+        assert!(auto_filter([1.0, 1.0], true, true) == io::Filter::Mipmap);
+        assert!(auto_filter([2.0, 2.0], true, true) == io::Filter::Mipmap);
+    }
+
+    #[test]
+    fn quarter_turns_stay_on_the_pixel_grid() {
+        let aligned = |json: &str| {
+            serde_json::from_str::<io::IoTransforms>(json)
+                .unwrap()
+                .axis_aligned()
+        };
+        assert!(aligned(r#"{"rotate": 90, "scale": [2, 3]}"#));
+        assert!(aligned(r#"[{"rotate": -180}, {"flip_x": true}]"#));
+        assert!(aligned(r#"{"matrix": [[0, -2, 0], [2, 0, 0], [0, 0, 1]]}"#));
+        assert!(!aligned(r#"{"rotate": 45}"#));
+        assert!(!aligned(r#"{"skew": [10, 0]}"#));
+        assert!(!aligned(r#"[{"scale": [2, 2]}, {"rotate": 30}]"#));
+    }
+
+    #[test]
+    fn swizzles_name_four_channels_or_constants() {
+        assert_eq!(swizzle_channels("bgra").unwrap(), [2.0, 1.0, 0.0, 3.0]);
+        assert_eq!(swizzle_channels("rrr1").unwrap(), [0.0, 0.0, 0.0, 5.0]);
+        assert_eq!(swizzle_channels("AGB0").unwrap(), [3.0, 1.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn invalid_swizzles_are_errors() {
+        for channels in ["", "rgb", "rgbar", "rgbx", "rg b", "xyzw", "rgb2"] {
+            assert!(
+                matches!(
+                    swizzle_channels(channels),
+                    Err(ShaderToyError::InvalidNode(_))
+                ),
+                "{}",
+                channels
+            );
+        }
+    }
 }
@@ -0,0 +1,238 @@
+use crate::error::ShaderToyError;
+use crate::programs;
+
+/// Signed distance field of a texture's alpha, computed on the GPU with the
+/// jump flooding algorithm. Distances are in pixels, positive outside the shape.
+pub struct DistanceField {
+    init: three_d::Program,
+    step: three_d::Program,
+    resolve: three_d::Program,
+    /// Seed textures, ping-ponged between jump flooding passes
+    seeds: [three_d::Texture2D; 2],
+    /// Output texture, the signed distance is stored in the red channel
+    output: three_d::Texture2D,
+    width: u32,
+    height: u32,
+}
+
+impl DistanceField {
+    pub fn new(context: &three_d::Context, width: u32, height: u32) -> Self {
+        // Store the nearest inside seed in `rg` and the nearest outside seed in `ba`
+        let init = three_d::Program::from_source(
+            context,
+            include_str!("./presets/fullscreen.vert"),
+            "
+                uniform sampler2D u_texture;
+                uniform vec2 u_resolution;
+                out vec4 outColor;
+
+                void main() {
+                    vec2 p = gl_FragCoord.xy;
+                    float a = texture(u_texture, p / u_resolution).a;
+                    outColor = a >= 0.5 ? vec4(p, -1.0, -1.0) : vec4(-1.0, -1.0, p);
+                }
+            ",
+        )
+        .unwrap();
+
+        let step = three_d::Program::from_source(
+            context,
+            include_str!("./presets/fullscreen.vert"),
+            "
+                uniform sampler2D u_seeds;
+                uniform vec2 u_resolution;
+                uniform float u_step;
+                out vec4 outColor;
+
+                void main() {
+                    vec2 p = gl_FragCoord.xy;
+                    vec4 best = vec4(-1.0, -1.0, -1.0, -1.0);
+                    float best_in = 1e20;
+                    float best_out = 1e20;
+                    for (int y = -1; y <= 1; y++) {
+                        for (int x = -1; x <= 1; x++) {
+                            vec2 q = p + vec2(float(x), float(y)) * u_step;
+                            if (any(lessThan(q, vec2(0.0))) || any(greaterThanEqual(q, u_resolution))) {
+                                continue;
+                            }
+                            vec4 s = texture(u_seeds, q / u_resolution);
+                            if (s.x >= 0.0) {
+                                float d = distance(p, s.xy);
+                                if (d < best_in) {
+                                    best_in = d;
+                                    best.xy = s.xy;
+                                }
+                            }
+                            if (s.z >= 0.0) {
+                                float d = distance(p, s.zw);
+                                if (d < best_out) {
+                                    best_out = d;
+                                    best.zw = s.zw;
+                                }
+                            }
+                        }
+                    }
+                    outColor = best;
+                }
+            ",
+        )
+        .unwrap();
+
+        let resolve = three_d::Program::from_source(
+            context,
+            include_str!("./presets/fullscreen.vert"),
+            "
+                uniform sampler2D u_texture;
+                uniform sampler2D u_seeds;
+                uniform vec2 u_resolution;
+                out vec4 outColor;
+
+                void main() {
+                    vec2 p = gl_FragCoord.xy;
+                    vec2 uv = p / u_resolution;
+                    vec4 s = texture(u_seeds, uv);
+                    float a = texture(u_texture, uv).a;
+                    float to_inside = s.x >= 0.0 ? distance(p, s.xy) : 1e6;
+                    float to_outside = s.z >= 0.0 ? distance(p, s.zw) : 1e6;
+
+                    // The edge lies halfway between pixel centers
+                    bool inside = a >= 0.5;
+                    float d = inside ? 0.5 - to_outside : to_inside - 0.5;
+                    // Refine pixels along the edge from their coverage
+                    if ((inside ? to_outside : to_inside) <= 1.0) {
+                        d = 0.5 - a;
+                    }
+                    outColor = vec4(d, 0.0, 0.0, 1.0);
+                }
+            ",
+        )
+        .unwrap();
+
+        Self {
+            init,
+            step,
+            resolve,
+            seeds: [
+                new_float_texture(context, width, height),
+                new_float_texture(context, width, height),
+            ],
+            output: new_float_texture(context, width, height),
+            width,
+            height,
+        }
+    }
+
+    /// Compute the signed distance field of `input`'s alpha.
+    /// Distances are exact up to `max_distance` pixels, farther pixels may saturate.
+    pub fn compute(
+        &mut self,
+        context: &three_d::Context,
+        input: &three_d::Texture2D,
+        max_distance: f32,
+    ) -> Result<&three_d::Texture2D, ShaderToyError> {
+        let viewport = three_d::Viewport::new_at_origo(self.width, self.height);
+        let u_resolution = three_d::vec2(self.width as f32, self.height as f32);
+        let (geom, _) = programs::fullscreen_quad(context);
+        let clear_state = three_d::ClearState::color(-1.0, -1.0, -1.0, -1.0);
+
+        self.init.use_uniform("u_resolution", u_resolution);
+        self.init.use_texture("u_texture", input);
+        self.init.use_vertex_attribute("a_position", &geom);
+        self.seeds[0]
+            .as_color_target(None)
+            .clear(clear_state)
+            .write(|| {
+                self.init.draw_arrays(
+                    three_d::RenderStates::default(),
+                    viewport,
+                    geom.vertex_count(),
+                );
+                Ok::<(), ShaderToyError>(())
+            })?;
+
+        let mut current = 0;
+        for step in jump_steps(max_distance, self.width, self.height) {
+            let (first, second) = self.seeds.split_at_mut(1);
+            let (read, write) = if current == 0 {
+                (&first[0], &mut second[0])
+            } else {
+                (&second[0], &mut first[0])
+            };
+            self.step.use_uniform("u_resolution", u_resolution);
+            self.step.use_uniform("u_step", step as f32);
+            self.step.use_texture("u_seeds", read);
+            self.step.use_vertex_attribute("a_position", &geom);
+            write.as_color_target(None).clear(clear_state).write(|| {
+                self.step.draw_arrays(
+                    three_d::RenderStates::default(),
+                    viewport,
+                    geom.vertex_count(),
+                );
+                Ok::<(), ShaderToyError>(())
+            })?;
+            current = 1 - current;
+        }
+
+        self.resolve.use_uniform("u_resolution", u_resolution);
+        self.resolve.use_texture("u_texture", input);
+        self.resolve.use_texture("u_seeds", &self.seeds[current]);
+        self.resolve.use_vertex_attribute("a_position", &geom);
+        self.output
+            .as_color_target(None)
+            .clear(three_d::ClearState::default())
+            .write(|| {
+                self.resolve.draw_arrays(
+                    three_d::RenderStates::default(),
+                    viewport,
+                    geom.vertex_count(),
+                );
+                Ok::<(), ShaderToyError>(())
+            })?;
+
+        Ok(&self.output)
+    }
+}
+
+/// Jump lengths of the flooding passes, halving from the largest distance of interest down to 1
+fn jump_steps(max_distance: f32, width: u32, height: u32) -> Vec<u32> {
+    let longest = width.max(height) as f32;
+    let first = (max_distance.clamp(1.0, longest).ceil() as u32).next_power_of_two();
+    std::iter::successors(Some(first), |step| Some(step / 2))
+        .take_while(|step| *step >= 1)
+        .collect()
+}
+
+/// Create a floating point texture able to hold pixel coordinates
+fn new_float_texture(context: &three_d::Context, width: u32, height: u32) -> three_d::Texture2D {
+    three_d::Texture2D::new_empty::<[f32; 4]>(
+        context,
+        width,
+        height,
+        three_d::Interpolation::Nearest,
+        three_d::Interpolation::Nearest,
+        None,
+        three_d::Wrapping::ClampToEdge,
+        three_d::Wrapping::ClampToEdge,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jumps_halve_down_from_the_max_distance() {
+        assert_eq!(jump_steps(10.0, 100, 100), [16, 8, 4, 2, 1]);
+        assert_eq!(jump_steps(8.0, 100, 100), [8, 4, 2, 1]);
+        assert_eq!(jump_steps(8.5, 100, 100), [16, 8, 4, 2, 1]);
+    }
+
+    #[test]
+    fn jumps_stay_within_the_texture() {
+        // Never farther than the longest side rounded up, and always at least one pass
+        assert_eq!(jump_steps(1000.0, 64, 20), [64, 32, 16, 8, 4, 2, 1]);
+        assert_eq!(jump_steps(1000.0, 20, 50), [64, 32, 16, 8, 4, 2, 1]);
+        assert_eq!(jump_steps(0.0, 64, 64), [1]);
+        assert_eq!(jump_steps(-5.0, 64, 64), [1]);
+    }
+}
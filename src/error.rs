@@ -10,4 +10,12 @@ pub enum ShaderToyError {
     FileLoad(#[from] std::io::Error),
     #[error("Headless error")]
     Headless(#[from] three_d::HeadlessError),
+    #[error("SVG loading error")]
+    Svg(#[from] resvg::usvg::Error),
+    #[error("Invalid node: {0}")]
+    InvalidNode(String),
+    #[error("Image comparison error: {0}")]
+    Compare(String),
+    #[error("Invalid arguments: {0}")]
+    Usage(String),
 }
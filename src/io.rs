@@ -6,19 +6,106 @@ pub fn resolve_resource_path(
 ) -> std::path::PathBuf {
     let parent_dir = parent_dir.to_path_buf();
     let resolved = parent_dir.join(resource_path);
-    println!("resolve {} = {}", resource_path, resolved.to_str().unwrap());
+    log::debug!("Resolve {} = {}", resource_path, resolved.to_str().unwrap());
     resolved
 }
 
-#[derive(Clone, Default, Serialize, Deserialize)]
+/// A single transform, or a list of transforms composed left to right like CSS `transform`
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IoTransforms {
+    Single(IoTransform),
+    List(Vec<IoTransform>),
+}
+
+impl Default for IoTransforms {
+    fn default() -> Self {
+        Self::Single(IoTransform::default())
+    }
+}
+
+impl IoTransforms {
+    pub fn as_slice(&self) -> &[IoTransform] {
+        match self {
+            IoTransforms::Single(transform) => std::slice::from_ref(transform),
+            IoTransforms::List(transforms) => transforms,
+        }
+    }
+
+    /// Overall magnitude of the scaling along each axis
+    pub fn scale(&self) -> [f32; 2] {
+        self.as_slice()
+            .iter()
+            .fold([1.0, 1.0], |[x, y], transform| {
+                let [sx, sy] = transform.scale_magnitude();
+                [x * sx, y * sy]
+            })
+    }
+
+    /// Whether pixels stay on a grid: no skew, and rotations by quarter turns only
+    pub fn axis_aligned(&self) -> bool {
+        self.as_slice()
+            .iter()
+            .all(|transform| match transform.matrix {
+                Some(m) => (m[0][1] == 0.0 && m[1][0] == 0.0) || (m[0][0] == 0.0 && m[1][1] == 0.0),
+                None => transform.rotate.rem_euclid(90.0) == 0.0 && transform.skew == [0.0, 0.0],
+            })
+    }
+}
+
+/// Affine transform of a layer in pixels, y pointing down, applied around `origin`
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub struct IoTransform {
+    /// Offset in pixels or percent of the layer size
     #[serde(default)]
-    pub translate: [f32; 2],
+    pub translate: [Length; 2],
+    /// Clockwise rotation in degrees
     #[serde(default)]
     pub rotate: f32,
     #[serde(default)]
     pub scale: Scale,
+    /// Skew angles along x and y in degrees, like CSS `skew()`
+    #[serde(default)]
+    pub skew: [f32; 2],
+    #[serde(default)]
+    pub flip_x: bool,
+    #[serde(default)]
+    pub flip_y: bool,
+    /// Pivot of the transform from the layer's top-left corner, in pixels or percent of the layer size
+    #[serde(default = "default_origin")]
+    pub origin: [Length; 2],
+    /// Rows of a 3×3 matrix in pixels used instead of the other fields, still applied around `origin`
+    #[serde(default)]
+    pub matrix: Option<[[f32; 3]; 3]>,
+}
+
+impl Default for IoTransform {
+    fn default() -> Self {
+        Self {
+            translate: Default::default(),
+            rotate: 0.0,
+            scale: Scale::default(),
+            skew: [0.0, 0.0],
+            flip_x: false,
+            flip_y: false,
+            origin: default_origin(),
+            matrix: None,
+        }
+    }
+}
+
+impl IoTransform {
+    fn scale_magnitude(&self) -> [f32; 2] {
+        match self.matrix {
+            Some(m) => [m[0][0].hypot(m[1][0]), m[0][1].hypot(m[1][1])],
+            None => [self.scale.x().abs(), self.scale.y().abs()],
+        }
+    }
+}
+
+fn default_origin() -> [Length; 2] {
+    [Length::Percent(50.0), Length::Percent(50.0)]
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -39,6 +126,72 @@ impl Scale {
     }
 }
 
+/// A length in pixels, written as a number or `"12px"`, or in percent, written as `"50%"`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "LengthRepr", into = "LengthRepr")]
+pub enum Length {
+    Pixels(f32),
+    Percent(f32),
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Self::Pixels(0.0)
+    }
+}
+
+impl Length {
+    /// Length in pixels, percentages being relative to `reference`
+    pub fn resolve(&self, reference: f32) -> f32 {
+        match self {
+            Length::Pixels(pixels) => *pixels,
+            Length::Percent(percent) => percent / 100.0 * reference,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LengthRepr {
+    Number(f32),
+    Text(String),
+}
+
+impl TryFrom<LengthRepr> for Length {
+    type Error = String;
+
+    fn try_from(repr: LengthRepr) -> Result<Self, Self::Error> {
+        match repr {
+            LengthRepr::Number(pixels) => Ok(Length::Pixels(pixels)),
+            LengthRepr::Text(text) => {
+                let text = text.trim();
+                let (number, percent) = match text.strip_suffix('%') {
+                    Some(number) => (number, true),
+                    None => (text.strip_suffix("px").unwrap_or(text), false),
+                };
+                let number = number
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| format!("invalid length: {}", text))?;
+                Ok(if percent {
+                    Length::Percent(number)
+                } else {
+                    Length::Pixels(number)
+                })
+            }
+        }
+    }
+}
+
+impl From<Length> for LengthRepr {
+    fn from(length: Length) -> Self {
+        match length {
+            Length::Pixels(pixels) => LengthRepr::Number(pixels),
+            Length::Percent(percent) => LengthRepr::Text(format!("{}%", percent)),
+        }
+    }
+}
+
 // {
 //   "nodes": [
 //     {
@@ -75,6 +228,8 @@ impl Scale {
 pub enum IoNode {
     Composition(IoComposition),
     Image(IoImage),
+    Text(IoText),
+    Shape(IoShape),
     Shader {
         frag: String,
         vert: String,
@@ -89,20 +244,1252 @@ pub enum IoNode {
         offset: [f32; 2],
         color: [f32; 4],
     },
+    Sharpen {
+        amount: f32,
+    },
+    UnsharpMask {
+        amount: f32,
+        radius: f32,
+        #[serde(default)]
+        threshold: f32,
+    },
+    EdgeDetect {
+        #[serde(default)]
+        operator: EdgeOperator,
+    },
+    Emboss {
+        angle: f32,
+        strength: f32,
+    },
+    HighPass {
+        radius: f32,
+    },
+    /// User-defined N×N kernel, given row by row from the top
+    Convolution {
+        kernel: Vec<f32>,
+        #[serde(default)]
+        divisor: Option<f32>,
+        #[serde(default)]
+        bias: f32,
+    },
+    /// Edge-preserving blur averaging only pixels of similar color
+    Bilateral {
+        /// Standard deviation of the distance weights, in pixels
+        #[serde(default = "default_spatial_sigma")]
+        spatial_sigma: f32,
+        /// Standard deviation of the color difference weights, from 0 to 1
+        #[serde(default = "default_range_sigma")]
+        range_sigma: f32,
+    },
+    /// Out of focus blur of a camera lens, where bright spots spread into the aperture shape
+    LensBlur {
+        /// Blur radius in pixels, reached away from the focal range when there is a depth map
+        radius: f32,
+        #[serde(default)]
+        aperture: IoAperture,
+        /// Clockwise rotation of the aperture in degrees
+        #[serde(default)]
+        rotate: f32,
+        /// Exposure added to highlights in stops, making bokeh shapes stand out
+        #[serde(default)]
+        highlight_boost: f32,
+        /// Luminance from which pixels count as highlights
+        #[serde(default = "default_highlight_threshold")]
+        highlight_threshold: f32,
+        /// Taps per pixel, whatever the radius
+        #[serde(default = "default_lens_blur_samples")]
+        samples: u32,
+        /// Grayscale image or sub-composition stretched over the canvas giving the depth of each
+        /// pixel in its red channel
+        #[serde(default)]
+        depth_map: Option<Box<IoNode>>,
+        /// Depth in focus, from 0 to 1
+        #[serde(default)]
+        focal_distance: f32,
+        /// Span of depths around the focal distance which stay sharp
+        #[serde(default)]
+        focal_range: f32,
+    },
+    /// Per channel median of the surrounding square, removing speckles while keeping edges
+    Median {
+        /// Up to 3 pixels
+        radius: u32,
+    },
+    /// Painterly smoothing picking, around each pixel, the average of the most uniform sectors
+    Kuwahara {
+        radius: f32,
+        /// Stretch the sectors along the local edges, like brush strokes
+        #[serde(default)]
+        anisotropic: bool,
+        /// How strongly uniform sectors win over the others
+        #[serde(default = "default_kuwahara_sharpness")]
+        sharpness: f32,
+    },
+    Dilate {
+        radius: f32,
+        #[serde(default)]
+        shape: StructuringElement,
+    },
+    Erode {
+        radius: f32,
+        #[serde(default)]
+        shape: StructuringElement,
+    },
+    /// Outline around the alpha of the current result
+    Stroke {
+        width: f32,
+        color: [f32; 4],
+        #[serde(default)]
+        position: StrokePosition,
+    },
+    Tile(IoTile),
+    /// Darken, or tint, the image towards its edges
+    Vignette {
+        /// Opacity of the vignette color at the edges
+        #[serde(default = "default_vignette_amount")]
+        amount: f32,
+        #[serde(default)]
+        shape: VignetteShape,
+        /// Distance from the center where darkening starts, 1.0 being the middle of the edges
+        #[serde(default = "default_vignette_size")]
+        size: f32,
+        /// From 0 for a rounded rectangle to 1 for an ellipse
+        #[serde(default = "default_one")]
+        roundness: f32,
+        /// Width of the transition past `size`, in the same units
+        #[serde(default = "default_vignette_feather")]
+        feather: f32,
+        #[serde(default = "default_vignette_color")]
+        color: [f32; 3],
+    },
+    /// Photographic noise, strongest in the midtones
+    FilmGrain {
+        #[serde(default = "default_grain_amount")]
+        amount: f32,
+        /// Size of a grain in pixels
+        #[serde(default = "default_one")]
+        size: f32,
+        #[serde(default)]
+        seed: f32,
+        /// Change the grain on every render
+        #[serde(default)]
+        animated: bool,
+        /// Same noise on every channel
+        #[serde(default = "default_monochrome")]
+        monochrome: bool,
+    },
+    /// Red and blue scaled apart from the center, like a lens bending colors differently
+    ChromaticAberration {
+        /// Shift of red and blue in the corners, in pixels
+        #[serde(default = "default_aberration_amount")]
+        amount: f32,
+    },
+    /// Glow around the brightest areas
+    Bloom {
+        /// Brightness above which pixels glow
+        #[serde(default = "default_bloom_threshold")]
+        threshold: f32,
+        /// Width of the smooth transition around the threshold
+        #[serde(default = "default_bloom_knee")]
+        knee: f32,
+        /// Reach of the glow in pixels
+        #[serde(default = "default_bloom_radius")]
+        radius: f32,
+        #[serde(default = "default_one")]
+        intensity: f32,
+    },
+    /// Glow, halo and ghosts of a bright light in front of a lens
+    LensFlare {
+        /// Light position in pixels from the top-left corner
+        position: [f32; 2],
+        #[serde(default = "default_one")]
+        intensity: f32,
+        #[serde(default = "default_flare_color")]
+        color: [f32; 3],
+        /// Number of ghosts mirrored through the center
+        #[serde(default = "default_flare_ghosts")]
+        ghosts: u32,
+    },
+    /// Make pixels close to a key color transparent, like a green screen
+    ChromaKey {
+        key_color: [f32; 3],
+        /// Chroma distance to the key color under which pixels are fully transparent
+        #[serde(default = "default_key_tolerance")]
+        tolerance: f32,
+        /// Chroma distance past the tolerance over which pixels fade back in
+        #[serde(default = "default_key_softness")]
+        softness: f32,
+        /// Amount of key color spill removed from the remaining pixels, from 0 to 1
+        #[serde(default = "default_spill")]
+        spill: f32,
+        #[serde(default)]
+        despill: DespillMode,
+        /// Output the alpha as grayscale to check the matte
+        #[serde(default)]
+        matte_view: bool,
+    },
+    /// Make dark pixels transparent, or bright ones when inverted
+    LumaKey {
+        /// Luminance under which pixels are fully transparent, measured from white when inverted
+        threshold: f32,
+        /// Luminance past the threshold over which pixels fade back in
+        #[serde(default = "default_key_softness")]
+        softness: f32,
+        #[serde(default)]
+        invert: bool,
+        /// Output the alpha as grayscale to check the matte
+        #[serde(default)]
+        matte_view: bool,
+    },
+    /// Multiply colors by a matrix, like SVG `feColorMatrix`. Each row gives an output channel
+    /// from the red, green, blue and alpha inputs plus an offset.
+    ChannelMixer {
+        matrix: [[f32; 5]; 4],
+    },
+    /// Rearrange channels, e.g. "bgra" or "rrr1" to view the red channel.
+    /// Letters `r`, `g`, `b` and `a` pick an input channel, `0` and `1` are constants.
+    Swizzle {
+        channels: String,
+    },
+    /// Replace the alpha channel
+    SetAlpha {
+        source: AlphaSource,
+    },
+    /// Multiply colors by alpha
+    Premultiply,
+    /// Divide colors by alpha, the inverse of `Premultiply`
+    Unpremultiply,
+    /// Show what changed between two inputs, each an image or sub-composition stretched over
+    /// the canvas, or the pipeline so far when not set
+    Difference {
+        #[serde(default)]
+        a: Option<Box<IoNode>>,
+        #[serde(default)]
+        b: Option<Box<IoNode>>,
+        #[serde(default)]
+        mode: DifferenceMode,
+        /// Multiplier making small differences visible
+        #[serde(default = "default_one")]
+        amplify: f32,
+        /// Largest channel difference still counted as unchanged by the mask
+        #[serde(default)]
+        threshold: f32,
+    },
+    /// Map luminance onto a color ramp, for false color and duotones
+    GradientMap {
+        ramp: IoRamp,
+        /// Mix with the original colors, from 0 to 1
+        #[serde(default = "default_one")]
+        amount: f32,
+    },
+    /// Stretch each channel so the darkest and brightest pixels reach black and white, which
+    /// also removes color casts. Measured on the image so far at every render.
+    AutoLevels {
+        /// Percentage of pixels allowed to clip to black
+        #[serde(default = "default_clip")]
+        clip_shadows: f32,
+        /// Percentage of pixels allowed to clip to white
+        #[serde(default = "default_clip")]
+        clip_highlights: f32,
+    },
+    /// Like `AutoLevels`, stretching all channels together to keep colors
+    AutoContrast {
+        #[serde(default = "default_clip")]
+        clip_shadows: f32,
+        #[serde(default = "default_clip")]
+        clip_highlights: f32,
+    },
+    /// Gray-world white balance, assuming the image averages to a neutral gray
+    AutoWhiteBalance {
+        /// From 0 to 1
+        #[serde(default = "default_one")]
+        amount: f32,
+    },
+    /// Contrast limited adaptive histogram equalization (CLAHE) of luminance.
+    /// One tile and no clip limit give plain histogram equalization.
+    Equalize {
+        /// Number of tiles along each side, from 1 to 64, equalized separately and blended
+        #[serde(default = "default_equalize_tiles")]
+        tiles: u32,
+        /// Largest histogram bin as a multiple of the average one, limiting noise amplification.
+        /// 0 disables the limit.
+        #[serde(default = "default_clip_limit")]
+        clip_limit: f32,
+        /// Mix with the original luminance, from 0 to 1
+        #[serde(default = "default_one")]
+        amount: f32,
+    },
+    /// Simulate how viewers with a color vision deficiency see the image
+    ColorBlindness {
+        deficiency: Deficiency,
+        /// From 0 for normal vision to 1 for the full deficiency
+        #[serde(default = "default_one")]
+        severity: f32,
+    },
+    /// Give the image the colors of a reference image, for a consistent look over a batch
+    MatchColor {
+        /// Image file, measured once when loading
+        reference: String,
+        #[serde(default)]
+        mode: MatchMode,
+        /// Mix with the original colors, from 0 to 1
+        #[serde(default = "default_one")]
+        strength: f32,
+    },
+    /// Offset pixels by channels of an image or sub-composition stretched over the canvas,
+    /// like SVG `feDisplacementMap`
+    Displace {
+        source: Box<IoNode>,
+        /// Offset in pixels per unit of channel value, centered on 0.5 so channel values of 0
+        /// and 1 move pixels by ±scale/2
+        scale: f32,
+        #[serde(default = "default_x_channel")]
+        x_channel: Channel,
+        #[serde(default = "default_y_channel")]
+        y_channel: Channel,
+        #[serde(default)]
+        edge: EdgeMode,
+    },
+    /// Barrel distortion for a positive strength, pincushion for a negative one
+    LensDistort {
+        strength: f32,
+        #[serde(default = "default_zoom")]
+        zoom: f32,
+        /// Difference of strength of the red and blue channels from the green one
+        #[serde(default)]
+        chromatic_aberration: f32,
+        #[serde(default)]
+        edge: EdgeMode,
+    },
+    /// Twist around a center, most at the center and not at all past the radius
+    Swirl {
+        /// Clockwise twist at the center in degrees
+        angle: f32,
+        radius: f32,
+        /// Center in pixels from the top-left corner, the canvas center by default
+        #[serde(default)]
+        center: Option<[f32; 2]>,
+        #[serde(default)]
+        edge: EdgeMode,
+    },
+    /// Sine wave offsetting pixels, in circles around a center or along an axis
+    Ripple {
+        amplitude: f32,
+        wavelength: f32,
+        /// Phase of the wave in degrees, to animate it
+        #[serde(default)]
+        phase: f32,
+        #[serde(default)]
+        mode: RippleMode,
+        /// Center in pixels from the top-left corner of radial ripples, the canvas center by default
+        #[serde(default)]
+        center: Option<[f32; 2]>,
+        #[serde(default)]
+        edge: EdgeMode,
+    },
+    /// Wrap the image around the canvas center, or unwrap it. Angles start at the top and turn
+    /// clockwise across the width, radii grow from the center across the height.
+    Polar {
+        #[serde(default)]
+        direction: PolarDirection,
+        #[serde(default)]
+        edge: EdgeMode,
+    },
+    /// Crossfade the edges with the opposite ones so the result tiles without seams
+    MakeSeamless {
+        /// Width of the crossfaded bands, as a fraction of the size up to 0.5
+        #[serde(default = "default_seamless_blend")]
+        blend: f32,
+    },
+}
+
+fn default_vignette_amount() -> f32 {
+    0.5
+}
+
+fn default_vignette_size() -> f32 {
+    0.6
+}
+
+fn default_vignette_feather() -> f32 {
+    0.6
+}
+
+fn default_vignette_color() -> [f32; 3] {
+    [0.0, 0.0, 0.0]
+}
+
+fn default_grain_amount() -> f32 {
+    0.08
+}
+
+fn default_monochrome() -> bool {
+    true
+}
+
+fn default_aberration_amount() -> f32 {
+    2.0
+}
+
+fn default_bloom_threshold() -> f32 {
+    0.8
+}
+
+fn default_bloom_knee() -> f32 {
+    0.1
+}
+
+fn default_bloom_radius() -> f32 {
+    32.0
+}
+
+fn default_flare_color() -> [f32; 3] {
+    [1.0, 0.9, 0.75]
+}
+
+fn default_flare_ghosts() -> u32 {
+    4
+}
+
+fn default_spatial_sigma() -> f32 {
+    3.0
+}
+
+fn default_range_sigma() -> f32 {
+    0.1
+}
+
+fn default_kuwahara_sharpness() -> f32 {
+    8.0
+}
+
+fn default_highlight_threshold() -> f32 {
+    0.8
+}
+
+fn default_lens_blur_samples() -> u32 {
+    256
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VignetteShape {
+    /// Follows the aspect ratio of the canvas
+    #[default]
+    Fit,
+    Circle,
+}
+
+fn default_clip() -> f32 {
+    0.1
+}
+
+fn default_equalize_tiles() -> u32 {
+    8
+}
+
+fn default_clip_limit() -> f32 {
+    3.0
+}
+
+fn default_key_tolerance() -> f32 {
+    0.15
+}
+
+fn default_key_softness() -> f32 {
+    0.1
+}
+
+fn default_spill() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DifferenceMode {
+    /// Per channel absolute difference
+    #[default]
+    Absolute,
+    /// Luminance difference, red where `a` is brighter and blue where it is darker
+    Signed,
+    /// White where any channel changed by more than the threshold
+    Mask,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Deficiency {
+    /// No red cones
+    Protanopia,
+    /// No green cones
+    Deuteranopia,
+    /// No blue cones
+    Tritanopia,
+    /// No color vision at all
+    Achromatopsia,
+}
+
+impl Deficiency {
+    pub const ALL: [Deficiency; 4] = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+        Deficiency::Achromatopsia,
+    ];
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// Match the mean and standard deviation of each CIE L*a*b* channel, which moves the overall
+    /// tint and contrast while keeping the image's own tones
+    #[default]
+    Lab,
+    /// Match the full histogram of each RGB channel
+    Histogram,
+}
+
+/// Shape of the lens opening in a `LensBlur`
+#[derive(Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum IoAperture {
+    #[default]
+    Circle,
+    Hexagon,
+    /// Grayscale image or sub-composition whose luminance weights the blur
+    Image {
+        source: Box<IoNode>,
+    },
+}
+
+/// Where `SetAlpha` takes the new alpha from
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AlphaSource {
+    Luminance,
+    Red,
+    Constant {
+        value: f32,
+    },
+    /// An image or sub-composition stretched over the canvas
+    Input {
+        source: Box<IoNode>,
+        /// Channel used as alpha, the luminance when not set
+        #[serde(default)]
+        channel: Option<Channel>,
+    },
+}
+
+/// Colors of a `GradientMap`, from black to white
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum IoRamp {
+    Stops {
+        stops: Vec<IoGradientStop>,
+    },
+    Palette {
+        palette: Palette,
+    },
+    /// An image or sub-composition read from left to right along its middle row
+    Image {
+        source: Box<IoNode>,
+    },
+}
+
+/// Ready-made ramps
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    Viridis,
+    Magma,
+    Inferno,
+    Turbo,
+    Grayscale,
+}
+
+/// What the key color's dominant channel is limited to, to remove spill
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DespillMode {
+    /// Average of the two other channels
+    #[default]
+    Average,
+    /// Largest of the two other channels, the gentlest
+    Max,
+    /// Smallest of the two other channels, the strongest
+    Min,
+}
+
+fn default_x_channel() -> Channel {
+    Channel::R
+}
+
+fn default_y_channel() -> Channel {
+    Channel::G
+}
+
+fn default_zoom() -> f32 {
+    1.0
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+}
+
+/// What distortions read past the edges of the image
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeMode {
+    #[default]
+    Clamp,
+    Repeat,
+    Mirror,
+    Transparent,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RippleMode {
+    #[default]
+    Radial,
+    /// Waves along x, offsetting pixels vertically
+    Horizontal,
+    /// Waves along y, offsetting pixels horizontally
+    Vertical,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolarDirection {
+    /// Rectangular to polar
+    #[default]
+    ToPolar,
+    /// Polar to rectangular
+    ToRectangular,
+}
+
+fn default_seamless_blend() -> f32 {
+    0.25
+}
+
+/// An image or sub-composition repeated across the canvas, over the current result
+#[derive(Serialize, Deserialize)]
+pub struct IoTile {
+    /// `Image` or `Composition` node to repeat
+    pub source: Box<IoNode>,
+    #[serde(default)]
+    pub scale: Scale,
+    /// Position of a tile's top-left corner in pixels
+    #[serde(default)]
+    pub offset: [f32; 2],
+    /// Gap between tiles in pixels
+    #[serde(default)]
+    pub spacing: [f32; 2],
+    /// Clockwise rotation of the whole pattern around the canvas center, in degrees
+    #[serde(default)]
+    pub rotate: f32,
+    #[serde(default)]
+    pub pattern: TilePattern,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TilePattern {
+    #[default]
+    Grid,
+    /// Every other row shifted by half a tile
+    Brick,
+    /// Every other column shifted by half a tile
+    HalfDrop,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeOperator {
+    #[default]
+    Sobel,
+    Prewitt,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StructuringElement {
+    #[default]
+    Circle,
+    Square,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrokePosition {
+    #[default]
+    Outside,
+    Inside,
+    Center,
 }
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct IoImage {
     pub path: String,
     #[serde(default)]
-    pub transform: IoTransform,
+    pub transform: IoTransforms,
+    /// Drawn width of an SVG image in pixels, instead of its intrinsic width
+    #[serde(default)]
+    pub width: Option<f32>,
+    /// Drawn height of an SVG image in pixels, instead of its intrinsic height
+    #[serde(default)]
+    pub height: Option<f32>,
+    /// Color filled behind an SVG image
+    #[serde(default)]
+    pub background: Option<[f32; 4]>,
+    /// How the image is sized to the composition
+    #[serde(default)]
+    pub fit: Fit,
+    /// Where the image is placed in the composition
+    #[serde(default)]
+    pub align: Align,
+    /// Crop of the image in pixels: x, y, width, height from the top-left corner
+    #[serde(default)]
+    pub source_rect: Option<[f32; 4]>,
+    /// Nine-slice insets in pixels: top, right, bottom, left. The corners keep their size
+    /// while the edges and the center stretch with the image.
+    #[serde(default)]
+    pub slices: Option<[f32; 4]>,
+    /// How the image continues past its edges, seen when `source_rect` reaches outside of it
+    #[serde(default)]
+    pub wrap: Wrap,
+    /// Color past the edges with the `clamp-to-border` wrap
+    #[serde(default)]
+    pub border_color: [f32; 4],
+    /// Projective or mesh distortion, replacing `transform`
+    #[serde(default)]
+    pub warp: Option<IoWarp>,
+    /// Resampling used when the image is scaled
+    #[serde(default)]
+    pub filter: Filter,
+    #[serde(default)]
+    pub effects: Vec<IoLayerEffect>,
 }
 
+/// Project format written by this version, see `IoComposition::migrate`
+pub const FORMAT_VERSION: u32 = 2;
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct IoComposition {
-    pub nodes: Vec<IoNode>,
+    /// Project format, only read on the root composition. Projects without one are version 1,
+    /// whose transforms are migrated when loading, see `IoComposition::migrate`.
+    #[serde(default = "default_format_version")]
+    pub version: u32,
+    pub nodes: Vec<IoNamedNode>,
     pub width: u32,
     pub height: u32,
     #[serde(default)]
-    pub transform: IoTransform,
+    pub transform: IoTransforms,
+    /// Projective or mesh distortion, replacing `transform`
+    #[serde(default)]
+    pub warp: Option<IoWarp>,
+    #[serde(default)]
+    pub effects: Vec<IoLayerEffect>,
+}
+
+fn default_format_version() -> u32 {
+    1
+}
+
+impl IoComposition {
+    /// Rewrite a project of an older format as the current one, so it renders as before
+    pub fn migrate(&mut self) {
+        if self.version < 2 {
+            log::warn!(
+                "Project format version {} is migrated to {}, transforms are now in pixels with \
+                 y pointing down, add \"version\": {} once they are updated",
+                self.version,
+                FORMAT_VERSION,
+                FORMAT_VERSION
+            );
+            migrate_v1_transforms(&mut self.nodes, self.width, self.height);
+        }
+        self.version = FORMAT_VERSION;
+    }
+}
+
+/// Version 1 drew layers as `translate * rotate * scale` in the clip space of the parent
+/// canvas, y pointing up. Vertices had no homogeneous coordinate, so the translation was never
+/// applied. Express the rest as pixel matrices around the layer center, which is exact even for
+/// rotations of non-square canvases. Only images and compositions had transforms then.
+fn migrate_v1_transforms(nodes: &mut [IoNamedNode], width: u32, height: u32) {
+    for named in nodes.iter_mut() {
+        match &mut named.node {
+            IoNode::Image(image) => {
+                image.transform = migrate_v1_transform(&image.transform, width, height);
+            }
+            IoNode::Composition(composition) => {
+                composition.transform = migrate_v1_transform(&composition.transform, width, height);
+                migrate_v1_transforms(
+                    &mut composition.nodes,
+                    composition.width,
+                    composition.height,
+                );
+            }
+            _ => (),
+        }
+    }
+}
+
+fn migrate_v1_transform(transforms: &IoTransforms, width: u32, height: u32) -> IoTransforms {
+    // Clip space is stretched by the canvas aspect ratio compared to pixels
+    let aspect = height as f32 / width as f32;
+    let migrated = transforms
+        .as_slice()
+        .iter()
+        .map(|transform| {
+            if transform.translate != [Length::Pixels(0.0); 2] {
+                log::warn!("Format version 1 ignored translate, it is dropped to render as before");
+            }
+            let (sin, cos) = (-transform.rotate).to_radians().sin_cos();
+            let [scale_x, scale_y] = [transform.scale.x(), transform.scale.y()];
+            IoTransform {
+                // Conjugated by diag(width / 2, -height / 2), from clip space to pixels
+                matrix: Some([
+                    [cos * scale_x, sin * scale_y / aspect, 0.0],
+                    [-sin * scale_x * aspect, cos * scale_y, 0.0],
+                    [0.0, 0.0, 1.0],
+                ]),
+                ..IoTransform::default()
+            }
+        })
+        .collect();
+    IoTransforms::List(migrated)
+}
+
+/// Node of a composition, optionally named to inspect the result up to it
+#[derive(Serialize, Deserialize)]
+pub struct IoNamedNode {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub node: IoNode,
+}
+
+/// Distortion of a layer onto points given in pixels from the composition's top-left corner
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum IoWarp {
+    /// Perspective mapping of the layer's corners: top-left, top-right, bottom-right, bottom-left
+    CornerPin { corners: [[f32; 2]; 4] },
+    /// Smooth distortion through a lattice of `columns` × `rows` points, listed row by row
+    /// from the top-left. The layer's edges follow the outer points.
+    Mesh {
+        columns: u32,
+        rows: u32,
+        points: Vec<[f32; 2]>,
+        /// Triangle pairs per lattice cell along each axis
+        #[serde(default = "default_mesh_subdivisions")]
+        subdivisions: u32,
+    },
+}
+
+fn default_mesh_subdivisions() -> u32 {
+    16
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Wrap {
+    #[default]
+    Clamp,
+    Repeat,
+    MirroredRepeat,
+    ClampToBorder,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    /// Mip mapping when the image is drawn smaller than its pixels or warped, nearest when
+    /// magnified by a whole factor on a pixel grid, else linear
+    #[default]
+    Auto,
+    /// Blocky, for pixel art
+    Nearest,
+    Linear,
+    /// Trilinear filtering between mip levels
+    Mipmap,
+    Bicubic,
+    Lanczos3,
+    /// Average of the pixels covered, sharp and alias-free for both up and downscaling
+    Area,
+}
+
+/// Like CSS `object-fit`, relative to the composition size
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Fit {
+    #[default]
+    None,
+    Contain,
+    Cover,
+    Fill,
+    ScaleDown,
+}
+
+impl Fit {
+    /// Drawn size of content of `size` fitted into `bounds`
+    pub fn size(&self, size: [f32; 2], bounds: [f32; 2]) -> [f32; 2] {
+        let contain = (bounds[0] / size[0]).min(bounds[1] / size[1]);
+        let scale = match self {
+            Fit::None => 1.0,
+            Fit::Fill => return bounds,
+            Fit::Contain => contain,
+            Fit::Cover => (bounds[0] / size[0]).max(bounds[1] / size[1]),
+            Fit::ScaleDown => contain.min(1.0),
+        };
+        [size[0] * scale, size[1] * scale]
+    }
+}
+
+/// Either a named anchor or a fraction of the free space, `[0.0, 0.0]` being top-left
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Align {
+    Anchor(Anchor),
+    Fraction([f32; 2]),
+}
+
+impl Default for Align {
+    fn default() -> Self {
+        Self::Anchor(Anchor::Center)
+    }
+}
+
+impl Align {
+    pub fn fraction(&self) -> [f32; 2] {
+        match self {
+            Align::Fraction(fraction) => *fraction,
+            Align::Anchor(anchor) => match anchor {
+                Anchor::TopLeft => [0.0, 0.0],
+                Anchor::Top => [0.5, 0.0],
+                Anchor::TopRight => [1.0, 0.0],
+                Anchor::Left => [0.0, 0.5],
+                Anchor::Center => [0.5, 0.5],
+                Anchor::Right => [1.0, 0.5],
+                Anchor::BottomLeft => [0.0, 1.0],
+                Anchor::Bottom => [0.5, 1.0],
+                Anchor::BottomRight => [1.0, 1.0],
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// Text laid out with a TrueType/OpenType font, composited like an `Image`
+#[derive(Default, Serialize, Deserialize)]
+pub struct IoText {
+    pub text: String,
+    /// Path to a `.ttf` or `.otf` file
+    pub font: String,
+    /// Font size in pixels
+    pub size: f32,
+    #[serde(default = "default_text_color")]
+    pub color: [f32; 4],
+    /// Extra space between glyphs in pixels
+    #[serde(default)]
+    pub letter_spacing: f32,
+    /// Distance between baselines, as a multiple of `size`
+    #[serde(default = "default_line_height")]
+    pub line_height: f32,
+    /// Wrap words onto new lines past this width in pixels
+    #[serde(default)]
+    pub max_width: Option<f32>,
+    #[serde(default)]
+    pub align: TextAlign,
+    #[serde(default)]
+    pub transform: IoTransforms,
+    #[serde(default)]
+    pub effects: Vec<IoLayerEffect>,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+fn default_text_color() -> [f32; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+fn default_line_height() -> f32 {
+    1.2
+}
+
+/// Vector shape rasterized at the composition resolution, composited like an `Image`.
+/// Coordinates are in pixels relative to the center of the layer, with y pointing down.
+#[derive(Serialize, Deserialize)]
+pub struct IoShape {
+    pub geometry: IoGeometry,
+    #[serde(default)]
+    pub fill: Option<IoPaint>,
+    #[serde(default)]
+    pub stroke: Option<IoStroke>,
+    #[serde(default = "default_anti_alias")]
+    pub anti_alias: bool,
+    #[serde(default)]
+    pub transform: IoTransforms,
+    #[serde(default)]
+    pub effects: Vec<IoLayerEffect>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum IoGeometry {
+    Rect {
+        width: f32,
+        height: f32,
+        /// Corner radii: top-left, top-right, bottom-right, bottom-left
+        #[serde(default)]
+        radii: [f32; 4],
+    },
+    Ellipse {
+        width: f32,
+        height: f32,
+    },
+    /// Regular polygon with a vertex at the top
+    Polygon {
+        radius: f32,
+        sides: u32,
+    },
+    Star {
+        outer_radius: f32,
+        inner_radius: f32,
+        points: u32,
+    },
+    Line {
+        from: [f32; 2],
+        to: [f32; 2],
+    },
+    /// SVG path data, as in the `d` attribute of `<path>`
+    Path {
+        d: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum IoPaint {
+    Solid {
+        color: [f32; 4],
+    },
+    Linear {
+        stops: Vec<IoGradientStop>,
+        start: [f32; 2],
+        end: [f32; 2],
+    },
+    Radial {
+        stops: Vec<IoGradientStop>,
+        center: [f32; 2],
+        radius: f32,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IoStroke {
+    pub paint: IoPaint,
+    pub width: f32,
+    #[serde(default)]
+    pub join: LineJoin,
+    #[serde(default)]
+    pub cap: LineCap,
+    /// Alternating dash and gap lengths in pixels
+    #[serde(default)]
+    pub dash: Vec<f32>,
+    #[serde(default)]
+    pub dash_offset: f32,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+fn default_anti_alias() -> bool {
+    true
+}
+
+/// Photoshop-like layer style effect, applied to an `Image` or `Composition` layer.
+/// `spread` and `choke` are fractions of `size` (0.0–1.0), offsets are in pixels.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IoLayerEffect {
+    DropShadow {
+        color: [f32; 4],
+        size: f32,
+        #[serde(default)]
+        offset: [f32; 2],
+        #[serde(default)]
+        spread: f32,
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+        #[serde(default = "default_shadow_blend")]
+        blend: BlendMode,
+    },
+    InnerShadow {
+        color: [f32; 4],
+        size: f32,
+        #[serde(default)]
+        offset: [f32; 2],
+        #[serde(default)]
+        choke: f32,
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+        #[serde(default = "default_shadow_blend")]
+        blend: BlendMode,
+    },
+    OuterGlow {
+        color: [f32; 4],
+        size: f32,
+        #[serde(default)]
+        spread: f32,
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+        #[serde(default = "default_glow_blend")]
+        blend: BlendMode,
+    },
+    InnerGlow {
+        color: [f32; 4],
+        size: f32,
+        #[serde(default)]
+        choke: f32,
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+        #[serde(default = "default_glow_blend")]
+        blend: BlendMode,
+    },
+    BevelEmboss {
+        size: f32,
+        #[serde(default = "default_depth")]
+        depth: f32,
+        /// Light direction in degrees, counter-clockwise from +x
+        #[serde(default = "default_light_angle")]
+        angle: f32,
+        /// Light elevation in degrees above the layer
+        #[serde(default = "default_light_altitude")]
+        altitude: f32,
+        #[serde(default = "default_highlight_color")]
+        highlight_color: [f32; 4],
+        #[serde(default = "default_glow_blend")]
+        highlight_blend: BlendMode,
+        #[serde(default = "default_shadow_color")]
+        shadow_color: [f32; 4],
+        #[serde(default = "default_shadow_blend")]
+        shadow_blend: BlendMode,
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+    },
+    ColorOverlay {
+        color: [f32; 4],
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+        #[serde(default)]
+        blend: BlendMode,
+    },
+    GradientOverlay {
+        stops: Vec<IoGradientStop>,
+        /// Gradient direction in degrees, counter-clockwise from +x
+        #[serde(default)]
+        angle: f32,
+        #[serde(default = "default_opacity")]
+        opacity: f32,
+        #[serde(default)]
+        blend: BlendMode,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IoGradientStop {
+    pub offset: f32,
+    pub color: [f32; 4],
+}
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    Add,
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_depth() -> f32 {
+    1.0
+}
+
+fn default_light_angle() -> f32 {
+    120.0
+}
+
+fn default_light_altitude() -> f32 {
+    30.0
+}
+
+fn default_highlight_color() -> [f32; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_shadow_color() -> [f32; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+fn default_shadow_blend() -> BlendMode {
+    BlendMode::Multiply
+}
+
+fn default_glow_blend() -> BlendMode {
+    BlendMode::Screen
 }
@@ -0,0 +1,398 @@
+use three_d::SquareMatrix;
+
+use crate::composition;
+use crate::distance;
+use crate::error::ShaderToyError;
+use crate::io;
+use crate::programs;
+use crate::value;
+
+/// Largest number of gradient stops accepted by the gradient shaders
+pub const MAX_GRADIENT_STOPS: usize = 16;
+
+/// A single layer style effect, containing its program
+struct Effect {
+    program: three_d::Program,
+    uniforms: Vec<(String, value::UniformValue)>,
+}
+
+/// Stack of layer style effects applied to an `Image` or `Composition` layer
+/// before it is blended onto the composition
+pub struct LayerStyle {
+    effects: Vec<Effect>,
+    distance: distance::DistanceField,
+    max_distance: f32,
+    /// Textures ping-ponged between effects
+    scratch: [three_d::Texture2D; 2],
+    width: u32,
+    height: u32,
+}
+
+impl LayerStyle {
+    pub fn new(
+        context: &three_d::Context,
+        effects: &[io::IoLayerEffect],
+        width: u32,
+        height: u32,
+    ) -> Result<Self, ShaderToyError> {
+        // Render bottom-up like Photoshop does, whatever the order in the project
+        let mut sorted = effects.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|effect| match effect {
+            io::IoLayerEffect::GradientOverlay { .. } => 0,
+            io::IoLayerEffect::ColorOverlay { .. } => 1,
+            io::IoLayerEffect::InnerGlow { .. } => 2,
+            io::IoLayerEffect::InnerShadow { .. } => 3,
+            io::IoLayerEffect::BevelEmboss { .. } => 4,
+            io::IoLayerEffect::OuterGlow { .. } => 5,
+            io::IoLayerEffect::DropShadow { .. } => 6,
+        });
+
+        let mut max_distance: f32 = 1.0;
+        let mut loaded = vec![];
+        for effect in sorted {
+            let (frag, uniforms, distance) = load_effect(effect)?;
+            max_distance = max_distance.max(distance + 1.0);
+            let frag = format!(
+                "{}{}",
+                include_str!("./presets/layer_style/common.glsl"),
+                frag
+            );
+            loaded.push(Effect {
+                program: three_d::Program::from_source(
+                    context,
+                    include_str!("./presets/layer_style/layer_style.vert"),
+                    &frag,
+                )
+                .unwrap(),
+                uniforms,
+            });
+        }
+
+        Ok(Self {
+            effects: loaded,
+            distance: distance::DistanceField::new(context, width, height),
+            max_distance,
+            scratch: [
+                composition::new_texture(context, width, height),
+                composition::new_texture(context, width, height),
+            ],
+            width,
+            height,
+        })
+    }
+
+    /// Apply every effect to `layer` in place. `backdrop` is what the layer
+    /// will be composited onto, used by the blend modes of effects drawn behind it.
+    pub fn apply(
+        &mut self,
+        context: &three_d::Context,
+        programs: &programs::Programs,
+        layer: &mut three_d::Texture2D,
+        backdrop: &three_d::Texture2D,
+    ) -> Result<(), ShaderToyError> {
+        let clear_state = three_d::ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0);
+        let viewport = three_d::Viewport::new_at_origo(self.width, self.height);
+        let distance = self.distance.compute(context, layer, self.max_distance)?;
+
+        let mut current: Option<usize> = None;
+        for effect in self.effects.iter() {
+            let (first, second) = self.scratch.split_at_mut(1);
+            let (read, write) = match current {
+                None => (&*layer, &mut first[0]),
+                Some(0) => (&first[0], &mut second[0]),
+                Some(_) => (&second[0], &mut first[0]),
+            };
+            write.as_color_target(None).clear(clear_state).write(|| {
+                programs::apply_shader(
+                    context,
+                    &effect.program,
+                    &effect.uniforms,
+                    &[
+                        ("u_texture", read),
+                        ("u_distance", distance),
+                        ("u_backdrop", backdrop),
+                    ],
+                    viewport,
+                );
+                Ok::<(), ShaderToyError>(())
+            })?;
+            current = Some(current.map_or(0, |i| 1 - i));
+        }
+
+        if let Some(i) = current {
+            let result = &self.scratch[i];
+            layer.as_color_target(None).clear(clear_state).write(|| {
+                programs.draw_texture(context, result, three_d::Mat3::identity(), viewport);
+                Ok::<(), ShaderToyError>(())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fragment shader, uniforms and reach in pixels of a layer effect
+type LoadedEffect = (&'static str, Vec<(String, value::UniformValue)>, f32);
+
+fn load_effect(effect: &io::IoLayerEffect) -> Result<LoadedEffect, ShaderToyError> {
+    let loaded = match effect {
+        io::IoLayerEffect::DropShadow {
+            color,
+            size,
+            offset,
+            spread,
+            opacity,
+            blend,
+        } => (
+            include_str!("./presets/layer_style/drop_shadow.frag"),
+            vec![
+                ("u_color".to_string(), (*color).into()),
+                ("u_size".to_string(), (*size).into()),
+                ("u_offset".to_string(), (offset[0], offset[1]).into()),
+                ("u_spread".to_string(), (*spread).into()),
+                ("u_opacity".to_string(), (*opacity).into()),
+                ("u_blend".to_string(), blend_mode(*blend).into()),
+            ],
+            size + offset[0].hypot(offset[1]),
+        ),
+        io::IoLayerEffect::InnerShadow {
+            color,
+            size,
+            offset,
+            choke,
+            opacity,
+            blend,
+        } => (
+            include_str!("./presets/layer_style/inner_shadow.frag"),
+            vec![
+                ("u_color".to_string(), (*color).into()),
+                ("u_size".to_string(), (*size).into()),
+                ("u_offset".to_string(), (offset[0], offset[1]).into()),
+                ("u_choke".to_string(), (*choke).into()),
+                ("u_opacity".to_string(), (*opacity).into()),
+                ("u_blend".to_string(), blend_mode(*blend).into()),
+            ],
+            size + offset[0].hypot(offset[1]),
+        ),
+        io::IoLayerEffect::OuterGlow {
+            color,
+            size,
+            spread,
+            opacity,
+            blend,
+        } => (
+            include_str!("./presets/layer_style/outer_glow.frag"),
+            vec![
+                ("u_color".to_string(), (*color).into()),
+                ("u_size".to_string(), (*size).into()),
+                ("u_spread".to_string(), (*spread).into()),
+                ("u_opacity".to_string(), (*opacity).into()),
+                ("u_blend".to_string(), blend_mode(*blend).into()),
+            ],
+            *size,
+        ),
+        io::IoLayerEffect::InnerGlow {
+            color,
+            size,
+            choke,
+            opacity,
+            blend,
+        } => (
+            include_str!("./presets/layer_style/inner_glow.frag"),
+            vec![
+                ("u_color".to_string(), (*color).into()),
+                ("u_size".to_string(), (*size).into()),
+                ("u_choke".to_string(), (*choke).into()),
+                ("u_opacity".to_string(), (*opacity).into()),
+                ("u_blend".to_string(), blend_mode(*blend).into()),
+            ],
+            *size,
+        ),
+        io::IoLayerEffect::BevelEmboss {
+            size,
+            depth,
+            angle,
+            altitude,
+            highlight_color,
+            highlight_blend,
+            shadow_color,
+            shadow_blend,
+            opacity,
+        } => {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let (sin_altitude, cos_altitude) = altitude.to_radians().sin_cos();
+            (
+                include_str!("./presets/layer_style/bevel_emboss.frag"),
+                vec![
+                    ("u_size".to_string(), (*size).into()),
+                    ("u_depth".to_string(), (*depth).into()),
+                    (
+                        "u_light".to_string(),
+                        [cos * cos_altitude, sin * cos_altitude, sin_altitude].into(),
+                    ),
+                    ("u_highlight_color".to_string(), (*highlight_color).into()),
+                    ("u_blend".to_string(), blend_mode(*highlight_blend).into()),
+                    ("u_shadow_color".to_string(), (*shadow_color).into()),
+                    (
+                        "u_shadow_blend".to_string(),
+                        blend_mode(*shadow_blend).into(),
+                    ),
+                    ("u_opacity".to_string(), (*opacity).into()),
+                ],
+                size + 1.0,
+            )
+        }
+        io::IoLayerEffect::ColorOverlay {
+            color,
+            opacity,
+            blend,
+        } => (
+            include_str!("./presets/layer_style/color_overlay.frag"),
+            vec![
+                ("u_color".to_string(), (*color).into()),
+                ("u_opacity".to_string(), (*opacity).into()),
+                ("u_blend".to_string(), blend_mode(*blend).into()),
+            ],
+            0.0,
+        ),
+        io::IoLayerEffect::GradientOverlay {
+            stops,
+            angle,
+            opacity,
+            blend,
+        } => {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let mut uniforms = gradient_uniforms(stops)?;
+            uniforms.extend([
+                ("u_direction".to_string(), (cos, sin).into()),
+                ("u_opacity".to_string(), (*opacity).into()),
+                ("u_blend".to_string(), blend_mode(*blend).into()),
+            ]);
+            (
+                include_str!("./presets/layer_style/gradient_overlay.frag"),
+                uniforms,
+                0.0,
+            )
+        }
+    };
+    Ok(loaded)
+}
+
+/// Index of a blend mode in `blend()` of the layer style shaders
+pub fn blend_mode(mode: io::BlendMode) -> f32 {
+    match mode {
+        io::BlendMode::Normal => 0.0,
+        io::BlendMode::Multiply => 1.0,
+        io::BlendMode::Screen => 2.0,
+        io::BlendMode::Overlay => 3.0,
+        io::BlendMode::Darken => 4.0,
+        io::BlendMode::Lighten => 5.0,
+        io::BlendMode::Add => 6.0,
+    }
+}
+
+/// Build `u_stop_offsets`, `u_stop_colors` and `u_stop_count` from gradient stops
+pub fn gradient_uniforms(
+    stops: &[io::IoGradientStop],
+) -> Result<Vec<(String, value::UniformValue)>, ShaderToyError> {
+    if stops.is_empty() || stops.len() > MAX_GRADIENT_STOPS {
+        return Err(ShaderToyError::InvalidNode(format!(
+            "gradients need between 1 and {} stops, got {}",
+            MAX_GRADIENT_STOPS,
+            stops.len()
+        )));
+    }
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    Ok(vec![
+        (
+            "u_stop_offsets".to_string(),
+            stops.iter().map(|s| s.offset).collect::<Vec<_>>().into(),
+        ),
+        (
+            "u_stop_colors".to_string(),
+            stops.iter().map(|s| s.color).collect::<Vec<_>>().into(),
+        ),
+        ("u_stop_count".to_string(), (stops.len() as f32).into()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(offsets: &[f32]) -> Vec<io::IoGradientStop> {
+        offsets
+            .iter()
+            .map(|offset| io::IoGradientStop {
+                offset: *offset,
+                color: [*offset, 0.0, 0.0, 1.0],
+            })
+            .collect()
+    }
+
+    fn uniform<'a>(
+        uniforms: &'a [(String, value::UniformValue)],
+        name: &str,
+    ) -> &'a value::UniformValue {
+        &uniforms.iter().find(|(n, _)| n == name).unwrap().1
+    }
+
+    fn direction(angle: f32) -> three_d::Vec2 {
+        let effect = serde_json::from_str(&format!(
+            r#"{{"type": "GradientOverlay", "stops": [{{"offset": 0, "color": [0, 0, 0, 1]}}], "angle": {}}}"#,
+            angle
+        ))
+        .unwrap();
+        let (_, uniforms, _) = load_effect(&effect).unwrap();
+        match uniform(&uniforms, "u_direction") {
+            value::UniformValue::Vec2(direction) => *direction,
+            _ => panic!("u_direction is a vec2"),
+        }
+    }
+
+    #[test]
+    fn gradients_take_1_to_16_stops() {
+        assert!(gradient_uniforms(&[]).is_err());
+        assert!(gradient_uniforms(&stops(&[0.5])).is_ok());
+        assert!(gradient_uniforms(&stops(&[0.0; MAX_GRADIENT_STOPS])).is_ok());
+        assert!(gradient_uniforms(&stops(&[0.0; MAX_GRADIENT_STOPS + 1])).is_err());
+    }
+
+    #[test]
+    fn gradient_stops_are_sorted_with_their_colors() {
+        let uniforms = gradient_uniforms(&stops(&[0.8, 0.2, 1.0, 0.0])).unwrap();
+        let value::UniformValue::FloatArray(offsets) = uniform(&uniforms, "u_stop_offsets") else {
+            panic!("u_stop_offsets is a float array");
+        };
+        assert_eq!(offsets, &[0.0, 0.2, 0.8, 1.0]);
+        let value::UniformValue::Vec4Array(colors) = uniform(&uniforms, "u_stop_colors") else {
+            panic!("u_stop_colors is a vec4 array");
+        };
+        let reds: Vec<f32> = colors.iter().map(|color| color.x).collect();
+        assert_eq!(reds, [0.0, 0.2, 0.8, 1.0]);
+        assert!(matches!(
+            uniform(&uniforms, "u_stop_count"),
+            value::UniformValue::Float(count) if *count == 4.0
+        ));
+    }
+
+    #[test]
+    fn gradient_angle_is_counter_clockwise_from_x() {
+        for (angle, expected) in [
+            (0.0, (1.0, 0.0)),
+            (90.0, (0.0, 1.0)),
+            (180.0, (-1.0, 0.0)),
+            (-90.0, (0.0, -1.0)),
+            (45.0, (0.5f32.sqrt(), 0.5f32.sqrt())),
+        ] {
+            let actual = direction(angle);
+            assert!(
+                (actual.x - expected.0).abs() < 1e-6 && (actual.y - expected.1).abs() < 1e-6,
+                "{:?} != {:?}",
+                (actual.x, actual.y),
+                expected
+            );
+        }
+    }
+}
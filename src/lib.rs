@@ -1,9 +1,23 @@
+pub mod adjust;
+pub mod bloom;
+pub mod color;
+pub mod colormap;
+pub mod compare;
 pub mod composition;
+pub mod distance;
 pub mod error;
 pub mod io;
+pub mod layer_style;
+pub mod palette;
 pub mod programs;
+pub mod raster;
+pub mod shape;
+pub mod stats;
+pub mod svg;
 pub mod target;
+pub mod text;
 pub mod value;
+pub mod warp;
 
 use error::ShaderToyError;
 use io::IoComposition;
@@ -22,8 +36,9 @@ impl ShaderToy {
     ) -> Result<ShaderToy, ShaderToyError> {
         log::debug!("Load json: {:?}", json_path);
         let json = std::fs::read_to_string(json_path.clone())?;
-        let composition =
+        let mut composition =
             serde_json::from_str::<IoComposition>(&json).map_err(ShaderToyError::from)?;
+        composition.migrate();
         let parent_dir = json_path
             .parent()
             .unwrap_or_else(|| std::path::Path::new("."));
@@ -51,4 +66,73 @@ impl ShaderToy {
         self.root
             .render_to_file(context, &self.programs, output_path)
     }
+
+    /// Render the final output into an RGBA image
+    pub fn render_image(
+        &mut self,
+        context: &three_d::Context,
+    ) -> Result<image::RgbaImage, ShaderToyError> {
+        self.root.render_image(context, &self.programs)
+    }
+
+    /// Render the result up to and including the node called `name`, at the size of the
+    /// composition holding it
+    pub fn render_node(
+        &mut self,
+        context: &three_d::Context,
+        name: &str,
+    ) -> Result<image::RgbaImage, ShaderToyError> {
+        self.root
+            .render_node(context, &self.programs, name)?
+            .ok_or_else(|| ShaderToyError::InvalidNode(format!("no node named \"{}\"", name)))
+    }
+
+    /// Statistics of the final output, or of the result up to the node called `node`
+    pub fn stats(
+        &mut self,
+        context: &three_d::Context,
+        node: Option<&str>,
+    ) -> Result<stats::Stats, ShaderToyError> {
+        let image = match node {
+            Some(name) => self.render_node(context, name)?,
+            None => self.render_image(context)?,
+        };
+        Ok(stats::analyze(&image))
+    }
+
+    /// Contact sheet of the final output for normal vision followed by protanopia, deuteranopia,
+    /// tritanopia and achromatopsia
+    pub fn color_blindness_sheet(
+        &mut self,
+        context: &three_d::Context,
+        severity: f32,
+    ) -> Result<image::RgbaImage, ShaderToyError> {
+        self.root
+            .color_blindness_sheet(context, &self.programs, severity)
+    }
+
+    /// Dominant colors of the final output, most common first
+    pub fn palette(
+        &mut self,
+        context: &three_d::Context,
+        count: usize,
+        method: palette::Method,
+    ) -> Result<Vec<palette::PaletteColor>, ShaderToyError> {
+        let image = self.render_image(context)?;
+        Ok(palette::extract(&image, count, method))
+    }
+
+    /// Set a uniform on every shader of the project declaring it, for the next renders
+    pub fn set_uniform(&mut self, name: &str, value: value::UniformValue) {
+        self.root.set_uniform(name, &value);
+    }
+
+    /// Pass palette colors to the shaders as `vec4` uniforms `<prefix>_0`, `<prefix>_1`, ...
+    /// and their number as the float `<prefix>_count`
+    pub fn set_palette(&mut self, prefix: &str, colors: &[palette::PaletteColor]) {
+        for (index, color) in colors.iter().enumerate() {
+            self.set_uniform(&format!("{}_{}", prefix, index), color.to_vec4().into());
+        }
+        self.set_uniform(&format!("{}_count", prefix), (colors.len() as f32).into());
+    }
 }
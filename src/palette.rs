@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+
+/// How the dominant colors are found
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+    /// Lloyd's k-means, seeded with the median-cut colors
+    #[default]
+    KMeans,
+    /// Recursively split the color box with the most pixels along its widest channel
+    MedianCut,
+}
+
+impl std::str::FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kmeans" | "k-means" => Ok(Method::KMeans),
+            "median-cut" | "mediancut" => Ok(Method::MedianCut),
+            _ => Err(format!(
+                "unknown palette method \"{}\", expected kmeans or median-cut",
+                s
+            )),
+        }
+    }
+}
+
+/// One dominant color
+#[derive(Clone, Debug, Serialize)]
+pub struct PaletteColor {
+    pub rgb: [u8; 3],
+    /// `#rrggbb`
+    pub hex: String,
+    /// Fraction of the visible pixels grouped into this color, from 0 to 1: those nearest to it
+    /// for `KMeans`, those in its box for `MedianCut`
+    pub proportion: f64,
+}
+
+impl PaletteColor {
+    /// Color as a `vec4` uniform value, opaque
+    pub fn to_vec4(&self) -> [f32; 4] {
+        let [r, g, b] = self.rgb;
+        [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0]
+    }
+}
+
+/// Pixels are grouped into bins of 5 bits per channel to keep large renders fast. Each bin keeps
+/// the exact average of its pixels, so the palette is not quantized.
+const BITS: u32 = 5;
+const MAX_ITERATIONS: usize = 32;
+
+#[derive(Clone, Copy)]
+struct Bin {
+    color: [f64; 3],
+    count: f64,
+}
+
+/// Find up to `count` dominant colors of the visible pixels, most common first
+pub fn extract(image: &image::RgbaImage, count: usize, method: Method) -> Vec<PaletteColor> {
+    let bins = bins(image);
+    let total: f64 = bins.iter().map(|bin| bin.count).sum();
+    if bins.is_empty() || count == 0 {
+        return vec![];
+    }
+
+    let mut clusters = median_cut(&bins, count);
+    if method == Method::KMeans {
+        clusters = kmeans(&bins, clusters);
+    }
+
+    let mut colors: Vec<PaletteColor> = clusters
+        .into_iter()
+        .filter(|cluster| cluster.count > 0.0)
+        .map(|cluster| {
+            let rgb = cluster.color.map(|c| c.round().clamp(0.0, 255.0) as u8);
+            PaletteColor {
+                rgb,
+                hex: format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]),
+                proportion: cluster.count / total,
+            }
+        })
+        .collect();
+    colors.sort_by(|a, b| b.proportion.total_cmp(&a.proportion));
+    colors
+}
+
+/// Render the palette as a row of equally wide swatches
+pub fn swatch(colors: &[PaletteColor], width: u32, height: u32) -> image::RgbaImage {
+    image::RgbaImage::from_fn(width, height, |x, _| {
+        if colors.is_empty() {
+            return image::Rgba([0, 0, 0, 0]);
+        }
+        let index = (x as usize * colors.len() / width as usize).min(colors.len() - 1);
+        let [r, g, b] = colors[index].rgb;
+        image::Rgba([r, g, b, 255])
+    })
+}
+
+/// Group the visible pixels, ignoring fully transparent ones
+fn bins(image: &image::RgbaImage) -> Vec<Bin> {
+    let side = 1usize << BITS;
+    let mut sums = vec![[0.0f64; 4]; side * side * side];
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a == 0 {
+            continue;
+        }
+        let shift = 8 - BITS;
+        let index =
+            ((r >> shift) as usize * side + (g >> shift) as usize) * side + (b >> shift) as usize;
+        let sum = &mut sums[index];
+        sum[0] += r as f64;
+        sum[1] += g as f64;
+        sum[2] += b as f64;
+        sum[3] += 1.0;
+    }
+    sums.into_iter()
+        .filter(|sum| sum[3] > 0.0)
+        .map(|[r, g, b, n]| Bin {
+            color: [r / n, g / n, b / n],
+            count: n,
+        })
+        .collect()
+}
+
+fn mean(bins: &[Bin]) -> Bin {
+    let count: f64 = bins.iter().map(|bin| bin.count).sum();
+    let mut color = [0.0; 3];
+    for bin in bins {
+        for (c, v) in color.iter_mut().zip(bin.color) {
+            *c += v * bin.count / count;
+        }
+    }
+    Bin { color, count }
+}
+
+fn median_cut(bins: &[Bin], count: usize) -> Vec<Bin> {
+    let mut boxes: Vec<Vec<Bin>> = vec![bins.to_vec()];
+    while boxes.len() < count {
+        // Split the most populated box which still has more than one color
+        let Some(index) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .max_by(|(_, a), (_, b)| population(a).total_cmp(&population(b)))
+            .map(|(index, _)| index)
+        else {
+            break;
+        };
+        let mut split = boxes.swap_remove(index);
+
+        let range = |channel: usize| {
+            let values = split.iter().map(|bin| bin.color[channel]);
+            values.clone().fold(f64::MIN, f64::max) - values.fold(f64::MAX, f64::min)
+        };
+        let channel = (0..3)
+            .max_by(|a, b| range(*a).total_cmp(&range(*b)))
+            .unwrap();
+        split.sort_by(|a, b| a.color[channel].total_cmp(&b.color[channel]));
+
+        // Cut at the weighted median, keeping both halves non empty
+        let half = population(&split) / 2.0;
+        let mut seen = 0.0;
+        let cut = split
+            .iter()
+            .position(|bin| {
+                seen += bin.count;
+                seen >= half
+            })
+            .unwrap_or(0)
+            .clamp(0, split.len() - 2)
+            + 1;
+        let upper = split.split_off(cut);
+        boxes.push(split);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|b| mean(b)).collect()
+}
+
+fn population(bins: &[Bin]) -> f64 {
+    bins.iter().map(|bin| bin.count).sum()
+}
+
+fn kmeans(bins: &[Bin], mut centers: Vec<Bin>) -> Vec<Bin> {
+    let mut assignment = vec![usize::MAX; bins.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (bin, assigned) in bins.iter().zip(assignment.iter_mut()) {
+            let nearest = (0..centers.len())
+                .min_by(|a, b| distance(bin, &centers[*a]).total_cmp(&distance(bin, &centers[*b])))
+                .unwrap();
+            changed |= *assigned != nearest;
+            *assigned = nearest;
+        }
+
+        for (index, center) in centers.iter_mut().enumerate() {
+            let members: Vec<Bin> = bins
+                .iter()
+                .zip(assignment.iter())
+                .filter(|(_, assigned)| **assigned == index)
+                .map(|(bin, _)| *bin)
+                .collect();
+            // An emptied cluster keeps its color and drops out of the result
+            *center = if members.is_empty() {
+                Bin {
+                    color: center.color,
+                    count: 0.0,
+                }
+            } else {
+                mean(&members)
+            };
+        }
+        if !changed {
+            break;
+        }
+    }
+    centers
+}
+
+fn distance(a: &Bin, b: &Bin) -> f64 {
+    a.color
+        .iter()
+        .zip(b.color)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Image two pixels high with `n` columns of each color
+    fn stripes(colors: &[([u8; 4], u32)]) -> image::RgbaImage {
+        let width = colors.iter().map(|(_, n)| n).sum();
+        image::RgbaImage::from_fn(width, 2, |x, _| {
+            let mut x = x;
+            for (color, n) in colors {
+                if x < *n {
+                    return image::Rgba(*color);
+                }
+                x -= n;
+            }
+            unreachable!()
+        })
+    }
+
+    const METHODS: [Method; 2] = [Method::KMeans, Method::MedianCut];
+
+    #[test]
+    fn no_colors_requested() {
+        let image = stripes(&[([255, 0, 0, 255], 4)]);
+        for method in METHODS {
+            assert!(extract(&image, 0, method).is_empty());
+        }
+    }
+
+    #[test]
+    fn transparent_image_has_no_colors() {
+        let image = stripes(&[([255, 0, 0, 0], 4)]);
+        for method in METHODS {
+            assert!(extract(&image, 3, method).is_empty());
+        }
+    }
+
+    #[test]
+    fn more_colors_than_the_image_has() {
+        let image = stripes(&[
+            ([255, 0, 0, 255], 6),
+            ([0, 0, 255, 255], 2),
+            ([0, 255, 0, 0], 8),
+        ]);
+        for method in METHODS {
+            let colors = extract(&image, 8, method);
+            assert_eq!(colors.len(), 2);
+            assert_eq!(colors[0].rgb, [255, 0, 0]);
+            assert_eq!(colors[0].hex, "#ff0000");
+            assert_eq!(colors[0].proportion, 0.75);
+            assert_eq!(colors[1].rgb, [0, 0, 255]);
+            assert_eq!(colors[1].proportion, 0.25);
+        }
+    }
+
+    #[test]
+    fn proportions_sum_to_one() {
+        let image = image::RgbaImage::from_fn(32, 32, |x, y| {
+            image::Rgba([(x * 8) as u8, (y * 8) as u8, ((x + y) * 4) as u8, 255])
+        });
+        for method in METHODS {
+            let colors = extract(&image, 5, method);
+            assert_eq!(colors.len(), 5);
+            let sum: f64 = colors.iter().map(|color| color.proportion).sum();
+            assert!((sum - 1.0).abs() < 1e-9, "{:?}: {}", method, sum);
+            assert!(colors
+                .windows(2)
+                .all(|pair| pair[0].proportion >= pair[1].proportion));
+        }
+    }
+}
@@ -0,0 +1,33 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_spatial_sigma;
+uniform float u_range_sigma;
+
+out vec4 outColor;
+
+// Window half size never exceeds this, whatever the sigma
+const int MAX_RADIUS = 16;
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    vec4 center = texture(u_texture, uv);
+    int radius = min(int(ceil(2.0 * u_spatial_sigma)), MAX_RADIUS);
+    float spatial = -0.5 / max(u_spatial_sigma * u_spatial_sigma, 1e-4);
+    float range = -0.5 / max(u_range_sigma * u_range_sigma, 1e-6);
+
+    // Average premultiplied colors so transparent pixels don't darken edges
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int y = -radius; y <= radius; y++) {
+        for (int x = -radius; x <= radius; x++) {
+            vec2 d = vec2(float(x), float(y));
+            vec4 c = texture(u_texture, uv + d / u_resolution);
+            vec4 diff = c - center;
+            float w = exp(dot(d, d) * spatial + dot(diff, diff) * range);
+            sum += vec4(c.rgb * c.a, c.a) * w;
+            total += w;
+        }
+    }
+    vec4 c = sum / total;
+    outColor = c.a > 0.0 ? vec4(c.rgb / c.a, c.a) : vec4(0.0);
+}
@@ -4,6 +4,6 @@ out vec4 outColor;
 
 void main() {
     vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
-    float luminance = (0.2126 * t.r + 0.7152 * t.g + 0.0722 * t.b);
+    float luminance = relative_luminance(t.rgb);
     outColor = vec4(luminance, luminance, luminance, t.a);
 }
@@ -0,0 +1,18 @@
+uniform sampler2D u_texture;
+// Premultiplied glow at a lower resolution, filtered when sampled
+uniform sampler2D u_bloom;
+uniform vec2 u_resolution;
+uniform float u_intensity;
+
+out vec4 outColor;
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    vec4 t = texture(u_texture, uv);
+    vec3 glow = texture(u_bloom, uv).rgb * u_intensity;
+
+    // Added like light, so transparent areas gain the glow's opacity
+    float a = max(t.a, clamp(max(glow.r, max(glow.g, glow.b)), 0.0, 1.0));
+    vec3 rgb = t.rgb * t.a + glow;
+    outColor = vec4(a > 0.0 ? clamp(rgb / a, 0.0, 1.0) : vec3(0.0), a);
+}
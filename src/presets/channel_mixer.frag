@@ -0,0 +1,18 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Red, green and blue outputs from the red, green and blue inputs
+uniform mat3 u_rgb;
+// Red, green and blue outputs from the alpha input
+uniform vec3 u_rgb_alpha;
+// Alpha output from the red, green, blue and alpha inputs
+uniform vec4 u_alpha;
+uniform vec4 u_offset;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    vec3 rgb = u_rgb * t.rgb + u_rgb_alpha * t.a + u_offset.rgb;
+    float a = dot(u_alpha, t) + u_offset.a;
+    outColor = clamp(vec4(rgb, a), 0.0, 1.0);
+}
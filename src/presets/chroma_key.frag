@@ -0,0 +1,38 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform vec3 u_key_color;
+uniform float u_tolerance;
+uniform float u_softness;
+uniform float u_spill;
+// 0.0: average, 1.0: max, 2.0: min
+uniform float u_despill;
+// Index of the dominant channel of the key color, followed by the two others
+uniform vec3 u_channels;
+uniform float u_matte_view;
+
+out vec4 outColor;
+
+// Blue and red difference of Rec. 709, independent of brightness
+vec2 chroma(vec3 c) {
+    float luminance = relative_luminance(c);
+    return vec2((c.b - luminance) / 1.8556, (c.r - luminance) / 1.5748);
+}
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    float d = distance(chroma(t.rgb), chroma(u_key_color));
+    float alpha = t.a * smoothstep(u_tolerance, u_tolerance + max(u_softness, 1e-4), d);
+
+    if (u_matte_view == 1.0) {
+        outColor = vec4(vec3(alpha), 1.0);
+        return;
+    }
+
+    vec3 c = t.rgb;
+    float a = c[int(u_channels.y)];
+    float b = c[int(u_channels.z)];
+    float limit = u_despill == 0.0 ? (a + b) * 0.5 : u_despill == 1.0 ? max(a, b) : min(a, b);
+    int key = int(u_channels.x);
+    c[key] = mix(c[key], min(c[key], limit), u_spill);
+    outColor = vec4(c, alpha);
+}
@@ -0,0 +1,12 @@
+uniform float u_amount;
+
+void main() {
+    vec2 center = u_resolution * 0.5;
+    vec2 p = pixel() - center;
+    // Scale so the shift reaches `u_amount` pixels in the corners
+    float scale = u_amount / length(center);
+    vec4 r = sample_pixel(center + p * (1.0 - scale));
+    vec4 g = sample_pixel(center + p);
+    vec4 b = sample_pixel(center + p * (1.0 + scale));
+    outColor = vec4(r.r, g.g, b.b, max(g.a, max(r.a, b.a)));
+}
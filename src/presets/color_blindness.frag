@@ -0,0 +1,12 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Simulation matrix, applied in linear light
+uniform mat3 u_matrix;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    vec3 linear = clamp(u_matrix * to_linear(clamp(t.rgb, 0.0, 1.0)), 0.0, 1.0);
+    outColor = vec4(to_srgb(linear), t.a);
+}
@@ -0,0 +1,25 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_kernel[81];
+uniform float u_size;
+uniform float u_bias;
+
+out vec4 outColor;
+
+void main() {
+    vec2 pos0 = gl_FragCoord.xy / u_resolution;
+    int size = int(u_size);
+    int half_size = size / 2;
+    vec3 col = vec3(0.0, 0.0, 0.0);
+
+    // Kernel rows are given from the top, while gl_FragCoord grows upwards
+    for (int y = 0; y < size; y++) {
+        for (int x = 0; x < size; x++) {
+            vec2 offset = vec2(float(x - half_size), float(half_size - y)) / u_resolution;
+            col += texture(u_texture, pos0 + offset).rgb * u_kernel[y * size + x];
+        }
+    }
+
+    vec4 t = texture(u_texture, pos0);
+    outColor = vec4(col + u_bias, t.a);
+}
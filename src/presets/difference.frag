@@ -0,0 +1,27 @@
+uniform sampler2D u_texture1;
+uniform sampler2D u_texture2;
+// 0.0: absolute, 1.0: signed, 2.0: mask
+uniform float u_mode;
+uniform float u_amplify;
+uniform float u_threshold;
+in vec2 v_uv;
+out vec4 outColor;
+
+void main() {
+    vec4 c1 = texture(u_texture1, v_uv);
+    vec4 c2 = texture(u_texture2, v_uv);
+    // Premultiplied so changes of transparent pixels' colors don't count
+    vec4 diff = vec4(c1.rgb * c1.a, c1.a) - vec4(c2.rgb * c2.a, c2.a);
+
+    if (u_mode == 1.0) {
+        // Red where the first input is brighter, blue where it is darker
+        float d = relative_luminance(diff.rgb) * u_amplify;
+        outColor = vec4(max(d, 0.0), 0.0, max(-d, 0.0), 1.0);
+    } else if (u_mode == 2.0) {
+        vec4 d = abs(diff);
+        float changed = max(max(d.r, d.g), max(d.b, d.a)) > u_threshold ? 1.0 : 0.0;
+        outColor = vec4(vec3(changed), 1.0);
+    } else {
+        outColor = vec4(clamp(abs(diff.rgb) * u_amplify, 0.0, 1.0), 1.0);
+    }
+}
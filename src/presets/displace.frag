@@ -0,0 +1,10 @@
+uniform sampler2D u_source;
+uniform float u_scale;
+// Channels of the map offsetting x and y, as indices
+uniform vec2 u_channels;
+
+void main() {
+    vec4 map = texture(u_source, gl_FragCoord.xy / u_resolution);
+    vec2 offset = vec2(map[int(u_channels.x)], map[int(u_channels.y)]) - 0.5;
+    outColor = sample_pixel(pixel() + offset * u_scale);
+}
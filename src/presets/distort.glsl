@@ -0,0 +1,27 @@
+// Shared by the distortion presets, which work in pixels from the top-left corner, y pointing down
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// 0.0: clamp, 1.0: repeat, 2.0: mirror, 3.0: transparent
+uniform float u_edge;
+
+out vec4 outColor;
+
+vec4 sample_edge(vec2 uv) {
+    if (u_edge == 1.0) {
+        uv = fract(uv);
+    } else if (u_edge == 2.0) {
+        uv = 1.0 - abs(mod(uv, 2.0) - 1.0);
+    } else if (u_edge == 3.0 && (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0))))) {
+        return vec4(0.0);
+    }
+    return texture(u_texture, clamp(uv, 0.0, 1.0));
+}
+
+// Position of the current pixel
+vec2 pixel() {
+    return vec2(gl_FragCoord.x, u_resolution.y - gl_FragCoord.y);
+}
+
+vec4 sample_pixel(vec2 p) {
+    return sample_edge(vec2(p.x, u_resolution.y - p.y) / u_resolution);
+}
@@ -0,0 +1,114 @@
+uniform sampler2D u_texture;
+// 0: the texture's own sampler, 1: bicubic, 2: Lanczos 3, 3: area average
+uniform float u_filter;
+// 0: clamp, 1: repeat, 2: mirrored repeat, 3: border color
+uniform float u_wrap;
+uniform vec4 u_border;
+in vec2 v_uv;
+out vec4 outColor;
+
+const float PI = 3.14159265;
+// Taps per axis never exceed this, whatever the scale
+const int MAX_TAPS = 32;
+
+// Catmull-Rom cubic
+float cubic(float x) {
+    x = abs(x);
+    if (x < 1.0) {
+        return 1.5 * x * x * x - 2.5 * x * x + 1.0;
+    }
+    if (x < 2.0) {
+        return -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0;
+    }
+    return 0.0;
+}
+
+float sinc(float x) {
+    if (abs(x) < 1e-5) {
+        return 1.0;
+    }
+    x *= PI;
+    return sin(x) / x;
+}
+
+float lanczos3(float x) {
+    return abs(x) < 3.0 ? sinc(x) * sinc(x / 3.0) : 0.0;
+}
+
+// Overlap of the texel `d` away from the sampled point with the span [-extent, extent]
+float coverage(float d, float extent) {
+    return max(min(extent, d + 0.5) - max(-extent, d - 0.5), 0.0);
+}
+
+float tap_weight(float d, float scale, float extent) {
+    if (u_filter < 1.5) {
+        return cubic(d / scale);
+    }
+    if (u_filter < 2.5) {
+        return lanczos3(d / scale);
+    }
+    return coverage(d, extent);
+}
+
+// Texel coordinates wrapped like the sampler
+ivec2 wrap_texel(ivec2 t, ivec2 size) {
+    if (u_wrap > 0.5 && u_wrap < 1.5) {
+        return (t % size + size) % size;
+    }
+    if (u_wrap > 1.5 && u_wrap < 2.5) {
+        ivec2 m = (t % (2 * size) + 2 * size) % (2 * size);
+        return ivec2(
+            m.x >= size.x ? 2 * size.x - 1 - m.x : m.x,
+            m.y >= size.y ? 2 * size.y - 1 - m.y : m.y
+        );
+    }
+    return clamp(t, ivec2(0), size - 1);
+}
+
+vec4 resample(vec2 uv) {
+    vec2 size0 = vec2(textureSize(u_texture, 0));
+    // Texels of the full size texture covered by one output pixel along each axis
+    vec2 footprint = max(abs(dFdx(uv)), abs(dFdy(uv))) * size0;
+
+    // Start from the mip level where the footprint is a few texels, to bound the number of taps
+    float max_lod = max(ceil(log2(max(size0.x, size0.y))) - 1.0, 0.0);
+    float lod = clamp(floor(log2(max(footprint.x, footprint.y))) - 1.0, 0.0, max_lod);
+    int level = int(lod);
+    ivec2 size = textureSize(u_texture, level);
+    footprint *= vec2(size) / size0;
+
+    // Kernels widen when downscaling so they also low-pass the image
+    vec2 scale = max(footprint, vec2(1.0));
+    float radius = u_filter < 1.5 ? 2.0 : 3.0;
+    vec2 extent = u_filter < 2.5 ? radius * scale : footprint * 0.5;
+
+    // Texel space, with texel centers on integers
+    vec2 p = uv * vec2(size) - 0.5;
+    ivec2 lo = ivec2(ceil(p - extent - 0.5));
+    ivec2 hi = min(ivec2(floor(p + extent + 0.5)), lo + MAX_TAPS - 1);
+
+    // Filter premultiplied colors so transparent texels don't darken edges
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int y = lo.y; y <= hi.y; y++) {
+        float wy = tap_weight(float(y) - p.y, scale.y, extent.y);
+        for (int x = lo.x; x <= hi.x; x++) {
+            float w = wy * tap_weight(float(x) - p.x, scale.x, extent.x);
+            vec4 c = texelFetch(u_texture, wrap_texel(ivec2(x, y), size), level);
+            sum += vec4(c.rgb * c.a, c.a) * w;
+            total += w;
+        }
+    }
+
+    // Negative lobes can ring past the valid range
+    vec4 c = clamp(sum / max(total, 1e-6), 0.0, 1.0);
+    return c.a > 0.0 ? vec4(c.rgb / c.a, c.a) : vec4(0.0);
+}
+
+void main() {
+    if (u_wrap > 2.5 && (any(lessThan(v_uv, vec2(0.0))) || any(greaterThan(v_uv, vec2(1.0))))) {
+        outColor = u_border;
+        return;
+    }
+    outColor = u_filter < 0.5 ? texture(u_texture, v_uv) : resample(v_uv);
+}
@@ -0,0 +1,30 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// 2.0 for Sobel, 1.0 for Prewitt
+uniform float u_weight;
+
+out vec4 outColor;
+
+float luminance(vec2 p) {
+    return relative_luminance(texture(u_texture, p).rgb);
+}
+
+void main() {
+    vec2 pos0 = gl_FragCoord.xy / u_resolution;
+    vec2 d = 1.0 / u_resolution;
+
+    float tl = luminance(pos0 + vec2(-d.x, d.y));
+    float t = luminance(pos0 + vec2(0.0, d.y));
+    float tr = luminance(pos0 + vec2(d.x, d.y));
+    float l = luminance(pos0 + vec2(-d.x, 0.0));
+    float r = luminance(pos0 + vec2(d.x, 0.0));
+    float bl = luminance(pos0 + vec2(-d.x, -d.y));
+    float b = luminance(pos0 + vec2(0.0, -d.y));
+    float br = luminance(pos0 + vec2(d.x, -d.y));
+
+    float gx = (tr + u_weight * r + br) - (tl + u_weight * l + bl);
+    float gy = (tl + u_weight * t + tr) - (bl + u_weight * b + br);
+    float magnitude = clamp(length(vec2(gx, gy)), 0.0, 1.0);
+
+    outColor = vec4(magnitude, magnitude, magnitude, texture(u_texture, pos0).a);
+}
@@ -0,0 +1,35 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// One row per tile, from the bottom-left corner, mapping luminance levels to equalized ones
+uniform sampler2D u_lut;
+uniform float u_tiles;
+uniform float u_amount;
+
+out vec4 outColor;
+
+float lookup(vec2 tile, float level) {
+    int row = int(tile.y * u_tiles + tile.x);
+    return texelFetch(u_lut, ivec2(int(level), row), 0).r;
+}
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    float luminance = clamp(relative_luminance(t.rgb), 0.0, 1.0);
+    float level = floor(luminance * 255.0 + 0.5);
+
+    // Blend the mappings of the four nearest tile centers
+    vec2 position = clamp(gl_FragCoord.xy / u_resolution * u_tiles - 0.5, vec2(0.0), vec2(u_tiles - 1.0));
+    vec2 tile = floor(position);
+    vec2 next = min(tile + 1.0, vec2(u_tiles - 1.0));
+    vec2 f = position - tile;
+    float equalized = mix(
+        mix(lookup(tile, level), lookup(vec2(next.x, tile.y), level), f.x),
+        mix(lookup(vec2(tile.x, next.y), level), lookup(next, level), f.x),
+        f.y
+    );
+
+    float target = mix(luminance, equalized, clamp(u_amount, 0.0, 1.0));
+    // Scale colors to keep their hue, lifting black by adding gray
+    vec3 rgb = luminance > 0.0 ? t.rgb * (target / luminance) : vec3(target);
+    outColor = vec4(clamp(rgb, 0.0, 1.0), t.a);
+}
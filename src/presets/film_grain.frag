@@ -0,0 +1,49 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_amount;
+uniform float u_size;
+uniform float u_seed;
+// Renders so far, mixed into the seed when animated
+uniform float u_frame;
+uniform float u_animated;
+uniform float u_monochrome;
+
+out vec4 outColor;
+
+// Integer hash, stable across drivers unlike sin() based ones
+float hash(vec2 p, float channel) {
+    uvec3 v = uvec3(ivec3(floor(p), int(u_seed + u_frame * u_animated) * 3 + int(channel)));
+    v = v * 1664525u + 1013904223u;
+    v.x += v.y * v.z;
+    v.y += v.z * v.x;
+    v.z += v.x * v.y;
+    v ^= v >> 16u;
+    v.x += v.y * v.z;
+    return float(v.x & 0xffffu) / 65535.0;
+}
+
+// Value noise with smooth interpolation between grains
+float noise(vec2 p, float channel) {
+    vec2 i = floor(p);
+    vec2 f = smoothstep(0.0, 1.0, fract(p));
+    float a = hash(i, channel);
+    float b = hash(i + vec2(1.0, 0.0), channel);
+    float c = hash(i + vec2(0.0, 1.0), channel);
+    float d = hash(i + vec2(1.0, 1.0), channel);
+    return mix(mix(a, b, f.x), mix(c, d, f.x), f.y);
+}
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    vec2 p = gl_FragCoord.xy / max(u_size, 1e-3);
+
+    vec3 n = vec3(noise(p, 0.0));
+    if (u_monochrome == 0.0) {
+        n = vec3(n.r, noise(p, 1.0), noise(p, 2.0));
+    }
+
+    // Film grain shows the most in the midtones
+    float luminance = relative_luminance(t.rgb);
+    float weight = mix(0.25, 1.0, 4.0 * luminance * (1.0 - luminance));
+    outColor = vec4(clamp(t.rgb + (n - 0.5) * 2.0 * u_amount * weight, 0.0, 1.0), t.a);
+}
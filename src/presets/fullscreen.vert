@@ -0,0 +1,6 @@
+// Shared by the internal passes drawing the fullscreen quad, positions are already in clip space
+in vec4 a_position;
+
+void main() {
+    gl_Position = a_position;
+}
@@ -0,0 +1,20 @@
+uniform sampler2D u_texture;
+// Ramp read from left to right along its middle row
+uniform sampler2D u_source;
+uniform vec2 u_resolution;
+uniform float u_amount;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    float luminance = clamp(relative_luminance(t.rgb), 0.0, 1.0);
+
+    // Black and white land on the centers of the first and last pixels
+    float width = float(textureSize(u_source, 0).x);
+    float u = (luminance * (width - 1.0) + 0.5) / width;
+    vec4 ramp = textureLod(u_source, vec2(u, 0.5), 0.0);
+
+    float amount = clamp(u_amount, 0.0, 1.0);
+    outColor = vec4(mix(t.rgb, ramp.rgb, amount), t.a * mix(1.0, ramp.a, amount));
+}
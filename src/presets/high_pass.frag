@@ -0,0 +1,7 @@
+void main() {
+    vec2 pos0 = gl_FragCoord.xy / u_resolution;
+    vec4 t = texture(u_texture, pos0);
+    vec4 blurred = gaussian_blur(pos0);
+
+    outColor = vec4(clamp(t.rgb - blurred.rgb + 0.5, 0.0, 1.0), t.a);
+}
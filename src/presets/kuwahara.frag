@@ -0,0 +1,134 @@
+// Generalized Kuwahara filter with polynomial sector weights, optionally following the local
+// edge orientation, after Kyprianidis et al. "Anisotropic Kuwahara Filtering with Polynomial
+// Weighting Functions"
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_radius;
+uniform float u_anisotropic;
+uniform float u_sharpness;
+
+out vec4 outColor;
+
+const float PI = 3.14159265;
+const int MAX_RADIUS = 16;
+const int SECTORS = 8;
+
+// Squared positive part, the polynomial weight of a sector
+float weight(float z) {
+    z = max(z, 0.0);
+    return z * z;
+}
+
+vec3 pixel(vec2 p) {
+    return texture(u_texture, p / u_resolution).rgb;
+}
+
+// Structure tensor (xx, xy, yy) from Sobel gradients, smoothed over a small window
+vec3 structure_tensor(vec2 p) {
+    vec3 sum = vec3(0.0);
+    float total = 0.0;
+    for (int y = -2; y <= 2; y++) {
+        for (int x = -2; x <= 2; x++) {
+            vec2 q = p + vec2(float(x), float(y));
+            vec3 gx = (pixel(q + vec2(1.0, -1.0)) + 2.0 * pixel(q + vec2(1.0, 0.0)) + pixel(q + vec2(1.0, 1.0))
+                - pixel(q + vec2(-1.0, -1.0)) - 2.0 * pixel(q + vec2(-1.0, 0.0)) - pixel(q + vec2(-1.0, 1.0))) / 4.0;
+            vec3 gy = (pixel(q + vec2(-1.0, 1.0)) + 2.0 * pixel(q + vec2(0.0, 1.0)) + pixel(q + vec2(1.0, 1.0))
+                - pixel(q + vec2(-1.0, -1.0)) - 2.0 * pixel(q + vec2(0.0, -1.0)) - pixel(q + vec2(1.0, -1.0))) / 4.0;
+            float w = exp(-float(x * x + y * y) / 4.0);
+            sum += vec3(dot(gx, gx), dot(gx, gy), dot(gy, gy)) * w;
+            total += w;
+        }
+    }
+    return sum / total;
+}
+
+void main() {
+    vec2 p = gl_FragCoord.xy;
+    float radius = clamp(u_radius, 1.0, float(MAX_RADIUS));
+
+    // Map the filter's ellipse onto a disk of radius 0.5
+    mat2 to_disk = mat2(0.5 / radius, 0.0, 0.0, 0.5 / radius);
+    if (u_anisotropic == 1.0) {
+        vec3 t = structure_tensor(p);
+        float root = sqrt((t.x - t.z) * (t.x - t.z) + 4.0 * t.y * t.y);
+        float lambda1 = 0.5 * (t.x + t.z + root);
+        float lambda2 = 0.5 * (t.x + t.z - root);
+        // Along the edges, across the strongest gradient
+        vec2 v = vec2(lambda1 - t.x, -t.y);
+        vec2 direction = length(v) > 0.0 ? normalize(v) : vec2(0.0, 1.0);
+        float phi = -atan(direction.y, direction.x);
+        float anisotropy = lambda1 + lambda2 > 0.0 ? (lambda1 - lambda2) / (lambda1 + lambda2) : 0.0;
+
+        float a = radius * clamp(1.0 + anisotropy, 0.1, 2.0);
+        float b = radius * clamp(1.0 / (1.0 + anisotropy), 0.1, 2.0);
+        float c = cos(phi);
+        float s = sin(phi);
+        to_disk = mat2(0.5 / a, 0.0, 0.0, 0.5 / b) * mat2(c, s, -s, c);
+    }
+
+    // Sector weights overlap a little so the result is smooth across their borders
+    float zeta = 0.33;
+    float eta = (zeta + cos(PI / float(SECTORS))) / pow(sin(PI / float(SECTORS)), 2.0);
+
+    vec4 m[SECTORS];
+    vec3 s[SECTORS];
+    for (int k = 0; k < SECTORS; k++) {
+        m[k] = vec4(0.0);
+        s[k] = vec3(0.0);
+    }
+
+    // Anisotropic ellipses reach up to twice the radius
+    int extent = int(ceil(u_anisotropic == 1.0 ? radius * 2.0 : radius));
+    for (int y = -extent; y <= extent; y++) {
+        for (int x = -extent; x <= extent; x++) {
+            vec2 v = to_disk * vec2(float(x), float(y));
+            if (dot(v, v) > 0.25) {
+                continue;
+            }
+            vec4 c = texture(u_texture, (p + vec2(float(x), float(y))) / u_resolution);
+
+            float w[SECTORS];
+            float vxx = zeta - eta * v.x * v.x;
+            float vyy = zeta - eta * v.y * v.y;
+            w[0] = weight(v.y + vxx);
+            w[2] = weight(-v.x + vyy);
+            w[4] = weight(-v.y + vxx);
+            w[6] = weight(v.x + vyy);
+            vec2 r = 0.70710678 * vec2(v.x - v.y, v.x + v.y);
+            vxx = zeta - eta * r.x * r.x;
+            vyy = zeta - eta * r.y * r.y;
+            w[1] = weight(r.y + vxx);
+            w[3] = weight(-r.x + vyy);
+            w[5] = weight(-r.y + vxx);
+            w[7] = weight(r.x + vyy);
+
+            float sum = 0.0;
+            for (int k = 0; k < SECTORS; k++) {
+                sum += w[k];
+            }
+            // Gaussian falloff towards the edge of the disk
+            float g = exp(-3.125 * dot(v, v)) / max(sum, 1e-6);
+            for (int k = 0; k < SECTORS; k++) {
+                float wk = w[k] * g;
+                m[k] += vec4(c.rgb * wk, wk);
+                s[k] += c.rgb * c.rgb * wk;
+            }
+        }
+    }
+
+    // Weight each sector's mean by how uniform it is
+    vec4 result = vec4(0.0);
+    for (int k = 0; k < SECTORS; k++) {
+        if (m[k].w <= 0.0) {
+            continue;
+        }
+        vec3 mean = m[k].rgb / m[k].w;
+        vec3 variance = abs(s[k] / m[k].w - mean * mean);
+        float sigma2 = variance.r + variance.g + variance.b;
+        float w = 1.0 / (1.0 + pow(255.0 * sigma2, 0.5 * u_sharpness));
+        result += vec4(mean * w, w);
+    }
+
+    vec4 t = texture(u_texture, p / u_resolution);
+    outColor = vec4(result.w > 0.0 ? result.rgb / result.w : t.rgb, t.a);
+}
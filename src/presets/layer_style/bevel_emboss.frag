@@ -0,0 +1,34 @@
+uniform float u_size;
+uniform float u_depth;
+uniform vec3 u_light;
+uniform vec4 u_highlight_color;
+uniform vec4 u_shadow_color;
+uniform float u_shadow_blend;
+
+// Height of the bevel in pixels, rising from the edge to `u_size` pixels inside
+float height(vec2 p) {
+    return smoothstep(0.0, 1.0, clamp(-signed_distance(p) / u_size, 0.0, 1.0)) * u_size * u_depth;
+}
+
+void main() {
+    vec2 p = gl_FragCoord.xy;
+    vec4 layer = texture(u_texture, p / u_resolution);
+    if (u_size <= 0.0) {
+        outColor = layer;
+        return;
+    }
+
+    // Wide central differences smooth out the steps of the distance field
+    vec2 gradient = vec2(
+        height(p + vec2(2.0, 0.0)) - height(p - vec2(2.0, 0.0)),
+        height(p + vec2(0.0, 2.0)) - height(p - vec2(0.0, 2.0))
+    ) * 0.25;
+    vec3 normal = normalize(vec3(-gradient, 1.0));
+    float shade = dot(normal, u_light);
+    // A flat surface receives `u_light.z`, brighter is highlight and darker is shadow
+    float highlight = max(shade - u_light.z, 0.0) / max(1.0 - u_light.z, 1e-3);
+    float shadow = max(u_light.z - shade, 0.0) / max(u_light.z, 1e-3);
+
+    layer = apply_inner(layer, u_highlight_color.rgb, highlight * u_highlight_color.a, u_blend);
+    outColor = apply_inner(layer, u_shadow_color.rgb, shadow * u_shadow_color.a, u_shadow_blend);
+}
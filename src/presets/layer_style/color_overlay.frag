@@ -0,0 +1,6 @@
+uniform vec4 u_color;
+
+void main() {
+    vec4 layer = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    outColor = apply_inner(layer, u_color.rgb, u_color.a, u_blend);
+}
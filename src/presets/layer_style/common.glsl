@@ -0,0 +1,64 @@
+// Shared by every layer style effect, prepended to the effect's shader
+uniform sampler2D u_texture;
+uniform sampler2D u_distance;
+uniform sampler2D u_backdrop;
+uniform vec2 u_resolution;
+uniform float u_opacity;
+// 0: normal, 1: multiply, 2: screen, 3: overlay, 4: darken, 5: lighten, 6: add
+uniform float u_blend;
+
+out vec4 outColor;
+
+vec3 blend(vec3 base, vec3 color, float mode) {
+    if (mode == 1.0) {
+        return base * color;
+    } else if (mode == 2.0) {
+        return 1.0 - (1.0 - base) * (1.0 - color);
+    } else if (mode == 3.0) {
+        return mix(
+            2.0 * base * color,
+            1.0 - 2.0 * (1.0 - base) * (1.0 - color),
+            step(0.5, base)
+        );
+    } else if (mode == 4.0) {
+        return min(base, color);
+    } else if (mode == 5.0) {
+        return max(base, color);
+    } else if (mode == 6.0) {
+        return min(base + color, 1.0);
+    }
+    return color;
+}
+
+vec4 over(vec4 top, vec4 bottom) {
+    float a = top.a + bottom.a * (1.0 - top.a);
+    if (a == 0.0) {
+        return vec4(0.0, 0.0, 0.0, 0.0);
+    }
+    return vec4((top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / a, a);
+}
+
+// Signed distance to the layer's alpha edge in pixels, positive outside
+float signed_distance(vec2 p) {
+    return texture(u_distance, p / u_resolution).r;
+}
+
+// 1.0 up to `hard` * `size` pixels away, fading out to 0.0 at `size`
+float falloff(float x, float size, float hard) {
+    if (size <= 0.0) {
+        return 1.0 - step(0.5, x);
+    }
+    return 1.0 - smoothstep(size * hard, size, x);
+}
+
+// Effect drawn on top of the layer content, clipped to its alpha
+vec4 apply_inner(vec4 layer, vec3 color, float amount, float mode) {
+    return vec4(mix(layer.rgb, blend(layer.rgb, color, mode), amount * u_opacity), layer.a);
+}
+
+// Effect drawn behind the layer content, blended with what the layer is composited on
+vec4 apply_behind(vec4 layer, vec3 color, float amount, float mode) {
+    vec4 backdrop = texture(u_backdrop, gl_FragCoord.xy / u_resolution);
+    vec3 c = mix(color, blend(backdrop.rgb, color, mode), backdrop.a);
+    return over(layer, vec4(c, amount * u_opacity));
+}
@@ -0,0 +1,11 @@
+uniform vec4 u_color;
+uniform float u_size;
+uniform vec2 u_offset;
+uniform float u_spread;
+
+void main() {
+    vec4 layer = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    vec2 p = gl_FragCoord.xy - u_offset * vec2(1.0, -1.0);
+    float amount = falloff(max(signed_distance(p), 0.0), u_size, u_spread);
+    outColor = apply_behind(layer, u_color.rgb, amount * u_color.a, u_blend);
+}
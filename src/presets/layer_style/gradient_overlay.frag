@@ -0,0 +1,28 @@
+uniform float u_stop_offsets[16];
+uniform vec4 u_stop_colors[16];
+uniform float u_stop_count;
+uniform vec2 u_direction;
+
+vec4 gradient(float t) {
+    int count = int(u_stop_count);
+    if (t <= u_stop_offsets[0]) {
+        return u_stop_colors[0];
+    }
+    for (int i = 1; i < count; i++) {
+        if (t <= u_stop_offsets[i]) {
+            float span = max(u_stop_offsets[i] - u_stop_offsets[i - 1], 1e-6);
+            return mix(u_stop_colors[i - 1], u_stop_colors[i], (t - u_stop_offsets[i - 1]) / span);
+        }
+    }
+    return u_stop_colors[count - 1];
+}
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    vec4 layer = texture(u_texture, uv);
+    // Project onto the direction so the gradient spans the canvas corner to corner
+    float extent = abs(u_direction.x) + abs(u_direction.y);
+    float t = dot(uv - 0.5, u_direction) / extent + 0.5;
+    vec4 color = gradient(t);
+    outColor = apply_inner(layer, color.rgb, color.a, u_blend);
+}
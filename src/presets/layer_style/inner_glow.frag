@@ -0,0 +1,9 @@
+uniform vec4 u_color;
+uniform float u_size;
+uniform float u_choke;
+
+void main() {
+    vec4 layer = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    float amount = falloff(max(-signed_distance(gl_FragCoord.xy), 0.0), u_size, u_choke);
+    outColor = apply_inner(layer, u_color.rgb, amount * u_color.a, u_blend);
+}
@@ -0,0 +1,99 @@
+// Gathers a fixed number of taps on a golden angle spiral spread over the aperture, so the cost
+// doesn't grow with the radius
+uniform sampler2D u_texture;
+// Depth of each pixel in the red channel
+uniform sampler2D u_depth;
+// Weights of a custom aperture, over its whole square
+uniform sampler2D u_kernel;
+uniform vec2 u_resolution;
+uniform float u_radius;
+// 0.0: circle, 1.0: hexagon, 2.0: custom kernel
+uniform float u_aperture;
+uniform float u_rotation;
+uniform float u_boost;
+uniform float u_threshold;
+uniform float u_samples;
+uniform float u_has_depth;
+uniform float u_focal_distance;
+uniform float u_focal_range;
+
+out vec4 outColor;
+
+const float PI = 3.14159265;
+const float GOLDEN_ANGLE = 2.39996323;
+const int MAX_SAMPLES = 1024;
+
+// Blur radius of a pixel from its distance to the focal range
+float circle_of_confusion(vec2 uv) {
+    if (u_has_depth == 0.0) {
+        return u_radius;
+    }
+    float depth = texture(u_depth, uv).r;
+    float half_range = u_focal_range * 0.5;
+    float defocus = (abs(depth - u_focal_distance) - half_range) / max(1.0 - half_range, 1e-3);
+    return u_radius * clamp(defocus, 0.0, 1.0);
+}
+
+// Distance from the center to the edge of a unit hexagon, with a corner at angle 0
+float hexagon(float angle) {
+    return cos(PI / 6.0) / cos(mod(angle, PI / 3.0) - PI / 6.0);
+}
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    vec4 center = texture(u_texture, uv);
+    float size = circle_of_confusion(uv);
+    if (size < 0.5) {
+        outColor = center;
+        return;
+    }
+
+    // Clockwise on screen, where y points up
+    float c = cos(u_rotation);
+    float s = sin(u_rotation);
+    mat2 rotation = mat2(c, -s, s, c);
+
+    int count = clamp(int(u_samples), 1, MAX_SAMPLES);
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+    for (int i = 0; i < count; i++) {
+        // Evenly spread over the unit disk
+        float r = sqrt((float(i) + 0.5) / float(count));
+        float angle = float(i) * GOLDEN_ANGLE;
+        vec2 offset = vec2(cos(angle), sin(angle)) * r;
+        float w = 1.0;
+        if (u_aperture == 1.0) {
+            offset *= hexagon(angle);
+        } else if (u_aperture == 2.0) {
+            // Cover the kernel's square with the disk around it
+            offset *= sqrt(2.0);
+            if (any(greaterThan(abs(offset), vec2(1.0)))) {
+                continue;
+            }
+            vec4 k = textureLod(u_kernel, offset * 0.5 + 0.5, 0.0);
+            w = relative_luminance(k.rgb) * k.a;
+        }
+        offset = rotation * offset * size;
+
+        vec2 sample_uv = uv + offset / u_resolution;
+        vec4 t = texture(u_texture, sample_uv);
+        // Pixels whose own blur doesn't reach this far, like sharp ones, don't spread here
+        if (u_has_depth == 1.0) {
+            float distance = length(offset);
+            w *= smoothstep(distance - 1.0, distance + 1.0, circle_of_confusion(sample_uv));
+        }
+        // Brighter than white, as the highlights of a clipped photo probably were
+        t.rgb *= exp2(u_boost * smoothstep(u_threshold, 1.0, relative_luminance(t.rgb)));
+
+        // Premultiplied so transparent pixels don't darken edges
+        sum += vec4(t.rgb * t.a, t.a) * w;
+        total += w;
+    }
+
+    if (total <= 0.0) {
+        outColor = center;
+        return;
+    }
+    vec4 color = sum / total;
+    outColor = color.a > 0.0 ? vec4(clamp(color.rgb / color.a, 0.0, 1.0), color.a) : vec4(0.0);
+}
@@ -0,0 +1,20 @@
+uniform float u_strength;
+uniform float u_zoom;
+uniform float u_aberration;
+
+// Radial distortion of a position relative to the half diagonal
+vec2 distort(vec2 p, float k) {
+    return p * (1.0 + k * dot(p, p)) / u_zoom;
+}
+
+void main() {
+    vec2 center = u_resolution * 0.5;
+    float norm = length(center);
+    vec2 p = (pixel() - center) / norm;
+
+    // Red and blue are bent more and less than green, splitting colors towards the edges
+    vec4 r = sample_pixel(distort(p, u_strength + u_aberration) * norm + center);
+    vec4 g = sample_pixel(distort(p, u_strength) * norm + center);
+    vec4 b = sample_pixel(distort(p, u_strength - u_aberration) * norm + center);
+    outColor = vec4(r.r, g.g, b.b, (r.a + g.a + b.a) / 3.0);
+}
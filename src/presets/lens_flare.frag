@@ -0,0 +1,54 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Light position in pixels from the top-left corner
+uniform vec2 u_position;
+uniform float u_intensity;
+uniform vec3 u_color;
+uniform float u_ghosts;
+
+out vec4 outColor;
+
+const int MAX_GHOSTS = 16;
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    vec4 t = texture(u_texture, uv);
+
+    float scale = min(u_resolution.x, u_resolution.y);
+    vec2 p = vec2(gl_FragCoord.x, u_resolution.y - gl_FragCoord.y);
+    vec2 light = u_position;
+    vec2 center = u_resolution * 0.5;
+
+    // Glow and starburst around the light
+    vec2 d = (p - light) / scale;
+    float r = length(d);
+    float glow = 0.02 / (r + 0.02) - 0.02;
+    float angle = atan(d.y, d.x);
+    float rays = pow(abs(cos(angle * 6.0)), 40.0) * exp(-r * 12.0) * 0.5;
+    float streak = exp(-abs(d.y) * 300.0) * exp(-abs(d.x) * 4.0) * 0.4;
+    vec3 flare = u_color * (max(glow, 0.0) + rays + streak);
+
+    // Halo ring of fixed radius around the light
+    float halo = exp(-pow((r - 0.35) * 40.0, 2.0)) * 0.08;
+    flare += halo * vec3(0.6, 0.8, 1.0);
+
+    // Ghosts along the line from the light through the center, tinted like coated lenses
+    for (int i = 0; i < MAX_GHOSTS; i++) {
+        if (float(i) >= u_ghosts) {
+            break;
+        }
+        float f = float(i) + 1.0;
+        float position = -0.4 * f + 0.3 * mod(f, 2.0);
+        vec2 ghost = center + (light - center) * position;
+        float size = (0.02 + 0.03 * mod(f * 7.0, 3.0)) * scale;
+        float g = smoothstep(size, size * 0.7, distance(p, ghost)) * 0.12;
+        vec3 tint = 0.5 + 0.5 * cos(f * 1.7 + vec3(0.0, 2.1, 4.2));
+        flare += g * tint;
+    }
+
+    // Added like light, so transparent areas gain the flare's opacity
+    vec3 light_color = flare * u_intensity;
+    float a = max(t.a, clamp(max(light_color.r, max(light_color.g, light_color.b)), 0.0, 1.0));
+    vec3 base = t.rgb * t.a;
+    outColor = vec4(a > 0.0 ? clamp((base + light_color) / a, 0.0, 1.0) : vec3(0.0), a);
+}
@@ -0,0 +1,13 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Input levels mapped to black and white, per channel
+uniform vec3 u_low;
+uniform vec3 u_high;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    vec3 rgb = (t.rgb - u_low) / max(u_high - u_low, vec3(1.0 / 255.0));
+    outColor = vec4(clamp(rgb, 0.0, 1.0), t.a);
+}
@@ -0,0 +1,18 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_threshold;
+uniform float u_softness;
+uniform float u_invert;
+uniform float u_matte_view;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    float luminance = relative_luminance(t.rgb);
+    if (u_invert == 1.0) {
+        luminance = 1.0 - luminance;
+    }
+    float alpha = t.a * smoothstep(u_threshold, u_threshold + max(u_softness, 1e-4), luminance);
+    outColor = u_matte_view == 1.0 ? vec4(vec3(alpha), 1.0) : vec4(t.rgb, alpha);
+}
@@ -0,0 +1,5 @@
+// Shared by the presets reading luminance, with Rec. 709 weights
+float relative_luminance(vec3 c) {
+    return dot(c, vec3(0.2126, 0.7152, 0.0722));
+}
+
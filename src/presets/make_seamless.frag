@@ -0,0 +1,27 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Width of the crossfaded bands, as a fraction of the size
+uniform float u_blend;
+
+out vec4 outColor;
+
+// 1.0 on the edges, fading to 0.0 past the band
+float edge_weight(float x) {
+    float d = min(x, 1.0 - x);
+    return 1.0 - smoothstep(0.0, max(u_blend, 1e-4), d);
+}
+
+// Crossfade with the copy shifted by half the width, seamless from left to right
+vec4 seamless_row(vec2 uv) {
+    vec4 c = texture(u_texture, uv);
+    vec4 shifted = texture(u_texture, vec2(fract(uv.x + 0.5), uv.y));
+    return mix(c, shifted, edge_weight(uv.x));
+}
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    // Rows of a horizontally seamless image, crossfaded the same way vertically
+    vec4 c = seamless_row(uv);
+    vec4 shifted = seamless_row(vec2(uv.x, fract(uv.y + 0.5)));
+    outColor = mix(c, shifted, edge_weight(uv.y));
+}
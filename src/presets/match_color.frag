@@ -0,0 +1,44 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// CIE L*a*b* statistics of the image and of the reference
+uniform vec3 u_source_mean;
+uniform vec3 u_source_stddev;
+uniform vec3 u_reference_mean;
+uniform vec3 u_reference_stddev;
+uniform float u_strength;
+
+out vec4 outColor;
+
+// D65 white point
+const vec3 WHITE = vec3(0.95047, 1.0, 1.08883);
+
+vec3 rgb_to_lab(vec3 rgb) {
+    vec3 c = to_linear(rgb);
+    vec3 xyz = vec3(
+        dot(vec3(0.4124, 0.3576, 0.1805), c),
+        relative_luminance(c),
+        dot(vec3(0.0193, 0.1192, 0.9505), c)
+    ) / WHITE;
+    vec3 f = mix((24389.0 / 27.0 * xyz + 16.0) / 116.0, pow(xyz, vec3(1.0 / 3.0)), step(216.0 / 24389.0, xyz));
+    return vec3(116.0 * f.y - 16.0, 500.0 * (f.x - f.y), 200.0 * (f.y - f.z));
+}
+
+vec3 lab_to_rgb(vec3 lab) {
+    float fy = (lab.x + 16.0) / 116.0;
+    vec3 f = vec3(fy + lab.y / 500.0, fy, fy - lab.z / 200.0);
+    vec3 xyz = mix((116.0 * f - 16.0) * 27.0 / 24389.0, f * f * f, step(6.0 / 29.0, f)) * WHITE;
+    vec3 c = mat3(
+        3.2406, -0.9689, 0.0557,
+        -1.5372, 1.8758, -0.2040,
+        -0.4986, 0.0415, 1.0570
+    ) * xyz;
+    return to_srgb(clamp(c, 0.0, 1.0));
+}
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    vec3 lab = rgb_to_lab(clamp(t.rgb, 0.0, 1.0));
+    vec3 scale = u_reference_stddev / max(u_source_stddev, vec3(1e-3));
+    vec3 matched = lab_to_rgb((lab - u_source_mean) * scale + u_reference_mean);
+    outColor = vec4(mix(t.rgb, matched, clamp(u_strength, 0.0, 1.0)), t.a);
+}
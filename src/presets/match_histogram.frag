@@ -0,0 +1,18 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Matched value of each level, one channel per color
+uniform sampler2D u_lut;
+uniform float u_strength;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    ivec3 level = ivec3(floor(clamp(t.rgb, 0.0, 1.0) * 255.0 + 0.5));
+    vec3 matched = vec3(
+        texelFetch(u_lut, ivec2(level.r, 0), 0).r,
+        texelFetch(u_lut, ivec2(level.g, 0), 0).g,
+        texelFetch(u_lut, ivec2(level.b, 0), 0).b
+    );
+    outColor = vec4(mix(t.rgb, matched, clamp(u_strength, 0.0, 1.0)), t.a);
+}
@@ -0,0 +1,42 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_radius;
+
+out vec4 outColor;
+
+const int MAX_RADIUS = 3;
+const int MAX_SAMPLES = (2 * MAX_RADIUS + 1) * (2 * MAX_RADIUS + 1);
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    int radius = clamp(int(u_radius), 0, MAX_RADIUS);
+
+    vec4 samples[MAX_SAMPLES];
+    int count = 0;
+    for (int y = -radius; y <= radius; y++) {
+        for (int x = -radius; x <= radius; x++) {
+            samples[count] = texture(u_texture, uv + vec2(float(x), float(y)) / u_resolution);
+            count++;
+        }
+    }
+
+    // The median of each channel has as many samples below it as above it
+    vec4 median = samples[0];
+    for (int channel = 0; channel < 4; channel++) {
+        for (int i = 0; i < count; i++) {
+            float v = samples[i][channel];
+            int below = 0;
+            int equal = 0;
+            for (int j = 0; j < count; j++) {
+                float w = samples[j][channel];
+                below += w < v ? 1 : 0;
+                equal += w == v ? 1 : 0;
+            }
+            if (below <= count / 2 && below + equal > count / 2) {
+                median[channel] = v;
+                break;
+            }
+        }
+    }
+    outColor = median;
+}
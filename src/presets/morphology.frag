@@ -0,0 +1,27 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_radius;
+// 1.0 to dilate (maximum), 0.0 to erode (minimum)
+uniform float u_dilate;
+// 1.0 for a square structuring element, 0.0 for a circle
+uniform float u_square;
+
+out vec4 outColor;
+
+void main() {
+    vec2 pos0 = gl_FragCoord.xy / u_resolution;
+    float rr = u_radius * u_radius;
+    vec4 col = texture(u_texture, pos0);
+
+    for (float y = -u_radius; y <= u_radius; y++) {
+        for (float x = -u_radius; x <= u_radius; x++) {
+            if (u_square == 0.0 && x * x + y * y > rr) {
+                continue;
+            }
+            vec4 t = texture(u_texture, pos0 + vec2(x, y) / u_resolution);
+            col = u_dilate == 1.0 ? max(col, t) : min(col, t);
+        }
+    }
+
+    outColor = col;
+}
@@ -0,0 +1,19 @@
+const float TAU = 6.28318531;
+
+// 0.0: rectangular to polar, 1.0: polar to rectangular
+uniform float u_direction;
+
+void main() {
+    vec2 center = u_resolution * 0.5;
+    vec2 p = pixel();
+    if (u_direction == 0.0) {
+        // The canvas ellipse, angle from the top turning clockwise
+        vec2 d = (p - center) / center;
+        float angle = atan(d.x, -d.y);
+        outColor = sample_pixel(vec2(fract(angle / TAU), length(d)) * u_resolution);
+    } else {
+        float angle = p.x / u_resolution.x * TAU;
+        float radius = p.y / u_resolution.y;
+        outColor = sample_pixel(center + center * radius * vec2(sin(angle), -cos(angle)));
+    }
+}
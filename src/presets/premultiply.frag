@@ -0,0 +1,14 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_unpremultiply;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    if (u_unpremultiply == 1.0) {
+        outColor = t.a > 0.0 ? vec4(clamp(t.rgb / t.a, 0.0, 1.0), t.a) : vec4(0.0);
+    } else {
+        outColor = vec4(t.rgb * t.a, t.a);
+    }
+}
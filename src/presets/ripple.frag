@@ -0,0 +1,23 @@
+const float TAU = 6.28318531;
+
+uniform float u_amplitude;
+uniform float u_wavelength;
+uniform float u_phase;
+// 0.0: radial, 1.0: horizontal, 2.0: vertical
+uniform float u_mode;
+uniform vec2 u_center;
+
+void main() {
+    vec2 p = pixel();
+    vec2 offset;
+    if (u_mode == 0.0) {
+        vec2 d = p - u_center;
+        float r = length(d);
+        offset = r > 0.0 ? d / r * u_amplitude * sin(TAU * r / u_wavelength + u_phase) : vec2(0.0);
+    } else if (u_mode == 1.0) {
+        offset = vec2(0.0, u_amplitude * sin(TAU * p.x / u_wavelength + u_phase));
+    } else {
+        offset = vec2(u_amplitude * sin(TAU * p.y / u_wavelength + u_phase), 0.0);
+    }
+    outColor = sample_pixel(p + offset);
+}
@@ -0,0 +1,28 @@
+uniform sampler2D u_texture;
+uniform sampler2D u_source;
+uniform vec2 u_resolution;
+// 0.0: luminance, 1.0: red, 2.0: constant, 3.0: source channel, 4.0: source luminance
+uniform float u_mode;
+uniform float u_value;
+// Channel of the source as an index
+uniform float u_channel;
+
+out vec4 outColor;
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    vec4 t = texture(u_texture, uv);
+    float a = u_value;
+    if (u_mode == 0.0) {
+        a = relative_luminance(t.rgb);
+    } else if (u_mode == 1.0) {
+        a = t.r;
+    } else if (u_mode == 3.0) {
+        a = texture(u_source, uv)[int(u_channel)];
+    } else if (u_mode == 4.0) {
+        vec4 s = texture(u_source, uv);
+        // Transparent parts of the source mask like black
+        a = relative_luminance(s.rgb) * s.a;
+    }
+    outColor = vec4(t.rgb, clamp(a, 0.0, 1.0));
+}
@@ -0,0 +1,35 @@
+uniform sampler2D u_texture;
+uniform sampler2D u_distance;
+uniform vec2 u_resolution;
+uniform float u_width;
+uniform vec4 u_color;
+// 0.0: outside, 1.0: inside, 2.0: center
+uniform float u_position;
+
+out vec4 outColor;
+
+vec4 over(vec4 top, vec4 bottom) {
+    float a = top.a + bottom.a * (1.0 - top.a);
+    if (a == 0.0) {
+        return vec4(0.0, 0.0, 0.0, 0.0);
+    }
+    return vec4((top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / a, a);
+}
+
+void main() {
+    vec2 pos0 = gl_FragCoord.xy / u_resolution;
+    vec4 t = texture(u_texture, pos0);
+    // Signed distance to the alpha edge in pixels, positive outside
+    float d = texture(u_distance, pos0).r;
+
+    if (u_position == 0.0) {
+        float coverage = clamp(u_width + 0.5 - d, 0.0, 1.0);
+        outColor = over(t, vec4(u_color.rgb, u_color.a * coverage));
+    } else if (u_position == 1.0) {
+        float coverage = clamp(d + u_width + 0.5, 0.0, 1.0) * t.a;
+        outColor = over(vec4(u_color.rgb, u_color.a * coverage), t);
+    } else {
+        float coverage = clamp(u_width * 0.5 + 0.5 - abs(d), 0.0, 1.0);
+        outColor = over(vec4(u_color.rgb, u_color.a * coverage), t);
+    }
+}
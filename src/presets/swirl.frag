@@ -0,0 +1,13 @@
+uniform float u_angle;
+uniform float u_radius;
+uniform vec2 u_center;
+
+void main() {
+    vec2 p = pixel() - u_center;
+    float t = max(1.0 - length(p) / u_radius, 0.0);
+    // Sample backwards so the image turns clockwise
+    float a = -u_angle * t * t;
+    float c = cos(a);
+    float s = sin(a);
+    outColor = sample_pixel(u_center + vec2(c * p.x - s * p.y, s * p.x + c * p.y));
+}
@@ -0,0 +1,20 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Input channel index of each output channel, 4.0 for zero and 5.0 for one
+uniform vec4 u_channels;
+
+out vec4 outColor;
+
+float channel(vec4 t, float index) {
+    return index < 3.5 ? t[int(index)] : index - 4.0;
+}
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    outColor = vec4(
+        channel(t, u_channels.r),
+        channel(t, u_channels.g),
+        channel(t, u_channels.b),
+        channel(t, u_channels.a)
+    );
+}
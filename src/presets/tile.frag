@@ -0,0 +1,48 @@
+uniform sampler2D u_texture;
+uniform sampler2D u_source;
+uniform vec2 u_resolution;
+// Drawn size of a tile and distance between tiles, in pixels
+uniform vec2 u_tile_size;
+uniform vec2 u_cell;
+uniform vec2 u_offset;
+// Cosine and sine of the pattern rotation
+uniform vec2 u_rotation;
+// 0.0: grid, 1.0: brick, 2.0: half-drop
+uniform float u_pattern;
+
+out vec4 outColor;
+
+vec4 over(vec4 top, vec4 bottom) {
+    float a = top.a + bottom.a * (1.0 - top.a);
+    if (a == 0.0) {
+        return vec4(0.0, 0.0, 0.0, 0.0);
+    }
+    return vec4((top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / a, a);
+}
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+
+    // Pattern space in pixels with y pointing down, unrotated around the canvas center
+    vec2 center = u_resolution * 0.5;
+    vec2 p = vec2(gl_FragCoord.x, u_resolution.y - gl_FragCoord.y) - center;
+    p = vec2(u_rotation.x * p.x + u_rotation.y * p.y, -u_rotation.y * p.x + u_rotation.x * p.y);
+    p += center - u_offset;
+
+    if (u_pattern == 1.0) {
+        p.x -= mod(floor(p.y / u_cell.y), 2.0) * u_cell.x * 0.5;
+    } else if (u_pattern == 2.0) {
+        p.y -= mod(floor(p.x / u_cell.x), 2.0) * u_cell.y * 0.5;
+    }
+    vec2 local = p - floor(p / u_cell) * u_cell;
+
+    vec4 tile = vec4(0.0);
+    if (all(lessThan(local, u_tile_size))) {
+        vec2 uv = local / u_tile_size;
+        // Gradients of the unwrapped coordinates, so mip selection doesn't jump at tile edges
+        vec2 dx = dFdx(p) / u_tile_size;
+        vec2 dy = dFdy(p) / u_tile_size;
+        tile = textureGrad(u_source, vec2(uv.x, 1.0 - uv.y), vec2(dx.x, -dx.y), vec2(dy.x, -dy.y));
+    }
+    outColor = over(tile, t);
+}
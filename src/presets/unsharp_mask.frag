@@ -0,0 +1,16 @@
+uniform float u_amount;
+uniform float u_threshold;
+
+void main() {
+    vec2 pos0 = gl_FragCoord.xy / u_resolution;
+    vec4 t = texture(u_texture, pos0);
+    vec4 blurred = gaussian_blur(pos0);
+
+    vec3 detail = t.rgb - blurred.rgb;
+    vec3 diff = abs(detail);
+    if (max(diff.r, max(diff.g, diff.b)) < u_threshold) {
+        outColor = t;
+    } else {
+        outColor = vec4(clamp(t.rgb + detail * u_amount, 0.0, 1.0), t.a);
+    }
+}
@@ -0,0 +1,28 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+uniform float u_amount;
+// 0.0: fit to the canvas, 1.0: circle
+uniform float u_shape;
+uniform float u_size;
+uniform float u_roundness;
+uniform float u_feather;
+uniform vec3 u_color;
+
+out vec4 outColor;
+
+void main() {
+    vec2 uv = gl_FragCoord.xy / u_resolution;
+    vec4 t = texture(u_texture, uv);
+
+    // 1.0 in the middle of the edges, or of the shorter edges for circles
+    vec2 p = abs(uv * 2.0 - 1.0);
+    if (u_shape == 1.0) {
+        p *= u_resolution / min(u_resolution.x, u_resolution.y);
+    }
+    // Superellipse, squarer as the exponent grows
+    float n = mix(8.0, 2.0, clamp(u_roundness, 0.0, 1.0));
+    float d = pow(pow(p.x, n) + pow(p.y, n), 1.0 / n);
+
+    float v = smoothstep(u_size, u_size + max(u_feather, 1e-4), d) * u_amount;
+    outColor = vec4(mix(t.rgb, u_color, v), t.a);
+}
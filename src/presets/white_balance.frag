@@ -0,0 +1,12 @@
+uniform sampler2D u_texture;
+uniform vec2 u_resolution;
+// Linear light multiplier of each channel
+uniform vec3 u_gain;
+
+out vec4 outColor;
+
+void main() {
+    vec4 t = texture(u_texture, gl_FragCoord.xy / u_resolution);
+    vec3 linear = clamp(to_linear(t.rgb) * u_gain, 0.0, 1.0);
+    outColor = vec4(to_srgb(linear), t.a);
+}
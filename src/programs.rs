@@ -1,6 +1,101 @@
+use crate::value;
+
+/// Part of a texture and how it is laid out when drawn
+#[derive(Clone, Copy)]
+pub struct TextureRegion {
+    /// Source rectangle in texture pixels: x, y, width, height from the top-left corner
+    pub source: [f32; 4],
+    /// Drawn size in pixels
+    pub size: [f32; 2],
+    /// Offset in pixels of the drawn center from the viewport center, y pointing down
+    pub offset: [f32; 2],
+    /// Nine-slice insets in texture pixels: top, right, bottom, left
+    pub slices: [f32; 4],
+    /// Drawn size in pixels of the nine-slice insets
+    pub insets: [f32; 4],
+    pub sampling: Sampling,
+}
+
+/// How `draw_texture` reads a texture
+#[derive(Clone, Copy, Default)]
+pub struct Sampling {
+    pub resampling: Resampling,
+    pub wrap: Wrap,
+}
+
+/// How `draw_texture` samples textures: through their own sampler state, or a kernel in
+/// the shader. Kernels read mip levels so textures using them need mip maps.
+#[derive(Clone, Copy, Default)]
+pub enum Resampling {
+    #[default]
+    Sampler,
+    Bicubic,
+    Lanczos3,
+    /// Average of the texels under each pixel
+    Area,
+}
+
+/// What is drawn outside of the texture, the sampler's own wrapping having to match
+#[derive(Clone, Copy, Default)]
+pub enum Wrap {
+    #[default]
+    Clamp,
+    Repeat,
+    MirroredRepeat,
+    /// A constant color, in straight alpha
+    Border([f32; 4]),
+}
+
+impl Resampling {
+    fn uniform(&self) -> f32 {
+        match self {
+            Resampling::Sampler => 0.0,
+            Resampling::Bicubic => 1.0,
+            Resampling::Lanczos3 => 2.0,
+            Resampling::Area => 3.0,
+        }
+    }
+}
+
+impl TextureRegion {
+    /// The whole texture at its native size, centered
+    pub fn full(texture: &three_d::Texture2D) -> Self {
+        let (width, height) = (texture.width() as f32, texture.height() as f32);
+        Self {
+            source: [0.0, 0.0, width, height],
+            size: [width, height],
+            offset: [0.0, 0.0],
+            slices: [0.0; 4],
+            insets: [0.0; 4],
+            sampling: Sampling::default(),
+        }
+    }
+
+    /// Texture coordinates of the source rectangle: left, top, right, bottom
+    pub fn uv(&self, texture: &three_d::Texture2D) -> [f32; 4] {
+        let (width, height) = (texture.width() as f32, texture.height() as f32);
+        let [x, y, w, h] = self.source;
+        // Texture rows are stored bottom-up
+        [
+            x / width,
+            1.0 - y / height,
+            (x + w) / width,
+            1.0 - (y + h) / height,
+        ]
+    }
+}
+
+/// Textured triangles, positioned in pixels from the viewport center with y pointing down
+pub struct Mesh {
+    pub positions: Vec<three_d::Vec3>,
+    pub uvs: Vec<three_d::Vec2>,
+}
+
 pub struct Programs {
     draw_texture: three_d::Program,
     blend_textures: three_d::Program,
+    difference_textures: three_d::Program,
+    color_blindness: three_d::Program,
 }
 
 impl Programs {
@@ -15,36 +110,32 @@ impl Programs {
                 out vec2 v_uv;
 
                 void main() {
-                    gl_Position = vec4(u_matrix * a_position, 1.0);
+                    // Homogeneous so projective matrices are interpolated with perspective
+                    vec3 p = u_matrix * a_position;
+                    gl_Position = vec4(p.xy, 0.0, p.z);
                     v_uv = a_uv;
                 }
             ",
-            "
-                uniform sampler2D u_texture;
-                in vec2 v_uv;
-                out vec4 outColor;
-
-                void main() {
-                    outColor = texture(u_texture, v_uv);
-                    
-                }
-            ",
+            include_str!("./presets/draw_texture.frag"),
         )
         .unwrap();
 
+        // Shared by the programs reading two textures
+        let two_textures_vert = "
+            in vec4 a_position;
+            in vec2 a_uv;
+            out vec2 v_uv;
+
+            void main() {
+                gl_Position = a_position;
+                v_uv = a_uv;
+            }
+        ";
+
         // For blend_textures textures
         let blend_textures = three_d::Program::from_source(
             context,
-            "
-                in vec4 a_position;
-                in vec2 a_uv;
-                out vec2 v_uv;
-
-                void main() {
-                    gl_Position = a_position;
-                    v_uv = a_uv;
-                }
-            ",
+            two_textures_vert,
             "
                 uniform sampler2D u_texture1;
                 uniform sampler2D u_texture2;
@@ -60,9 +151,35 @@ impl Programs {
         )
         .unwrap();
 
+        // For difference_textures textures
+        let difference_textures = three_d::Program::from_source(
+            context,
+            two_textures_vert,
+            &format!(
+                "{}{}",
+                include_str!("./presets/luminance.glsl"),
+                include_str!("./presets/difference.frag")
+            ),
+        )
+        .unwrap();
+
+        // For color_blindness, the program of the ColorBlindness node
+        let color_blindness = three_d::Program::from_source(
+            context,
+            include_str!("./presets/color_blindness.vert"),
+            &format!(
+                "{}{}",
+                include_str!("./presets/srgb.glsl"),
+                include_str!("./presets/color_blindness.frag")
+            ),
+        )
+        .unwrap();
+
         Self {
             draw_texture,
             blend_textures,
+            difference_textures,
+            color_blindness,
         }
     }
 
@@ -73,35 +190,116 @@ impl Programs {
         matrix: three_d::Mat3,
         viewport: three_d::Viewport,
     ) {
-        let sx = texture.width() as f32 / viewport.width as f32;
-        let sy = texture.height() as f32 / viewport.height as f32;
-        let geom = three_d::VertexBuffer::new_with_data(
+        self.draw_texture_region(
             context,
-            &[
-                three_d::vec3(-sx, -sy, 0.0),
-                three_d::vec3(-sx, sy, 0.0),
-                three_d::vec3(sx, sy, 0.0),
-                three_d::vec3(-sx, -sy, 0.0),
-                three_d::vec3(sx, sy, 0.0),
-                three_d::vec3(sx, -sy, 0.0),
-            ],
+            texture,
+            &TextureRegion::full(texture),
+            matrix,
+            viewport,
         );
-        let a_uv = three_d::VertexBuffer::new_with_data(
+    }
+
+    /// Draw part of a texture at a given size, nine-slice scaled when the region has insets.
+    /// `matrix` transforms pixels from the viewport center, y pointing down.
+    pub fn draw_texture_region(
+        &self,
+        context: &three_d::Context,
+        texture: &three_d::Texture2D,
+        region: &TextureRegion,
+        matrix: three_d::Mat3,
+        viewport: three_d::Viewport,
+    ) {
+        let (texture_width, texture_height) = (texture.width() as f32, texture.height() as f32);
+        let [x, y, width, height] = region.source;
+        let [top, right, bottom, left] = region.slices;
+        let [drawn_width, drawn_height] = region.size;
+        // Shrink the drawn insets when they don't fit, like CSS border-image
+        let [inset_top, inset_right, inset_bottom, inset_left] = region.insets;
+        let fit_x = (drawn_width / (inset_left + inset_right)).min(1.0);
+        let fit_y = (drawn_height / (inset_top + inset_bottom)).min(1.0);
+
+        // Grid lines of the nine slices from the top-left corner, as (drawn, texture) pixels
+        let columns = [
+            (0.0, x),
+            (inset_left * fit_x, x + left),
+            (drawn_width - inset_right * fit_x, x + width - right),
+            (drawn_width, x + width),
+        ];
+        let rows = [
+            (0.0, y),
+            (inset_top * fit_y, y + top),
+            (drawn_height - inset_bottom * fit_y, y + height - bottom),
+            (drawn_height, y + height),
+        ];
+
+        let mut positions = vec![];
+        let mut uvs = vec![];
+        let mut vertex = |column: (f32, f32), row: (f32, f32)| {
+            positions.push(three_d::vec3(
+                region.offset[0] + column.0 - drawn_width * 0.5,
+                region.offset[1] + row.0 - drawn_height * 0.5,
+                1.0,
+            ));
+            // Texture rows are stored bottom-up
+            uvs.push(three_d::vec2(
+                column.1 / texture_width,
+                1.0 - row.1 / texture_height,
+            ));
+        };
+        for r in 0..3 {
+            for c in 0..3 {
+                vertex(columns[c], rows[r + 1]);
+                vertex(columns[c], rows[r]);
+                vertex(columns[c + 1], rows[r]);
+                vertex(columns[c], rows[r + 1]);
+                vertex(columns[c + 1], rows[r]);
+                vertex(columns[c + 1], rows[r + 1]);
+            }
+        }
+
+        self.draw_texture_mesh(
             context,
-            &[
-                three_d::vec2(0.0, 0.0),
-                three_d::vec2(0.0, 1.0),
-                three_d::vec2(1.0, 1.0),
-                three_d::vec2(0.0, 0.0),
-                three_d::vec2(1.0, 1.0),
-                three_d::vec2(1.0, 0.0),
-            ],
+            texture,
+            &Mesh { positions, uvs },
+            region.sampling,
+            matrix,
+            viewport,
+        );
+    }
+
+    /// Draw the triangles of a mesh textured by `texture`.
+    /// `matrix` transforms pixels from the viewport center, y pointing down.
+    pub fn draw_texture_mesh(
+        &self,
+        context: &three_d::Context,
+        texture: &three_d::Texture2D,
+        mesh: &Mesh,
+        sampling: Sampling,
+        matrix: three_d::Mat3,
+        viewport: three_d::Viewport,
+    ) {
+        let geom = three_d::VertexBuffer::new_with_data(context, &mesh.positions);
+        let a_uv = three_d::VertexBuffer::new_with_data(context, &mesh.uvs);
+        let to_clip = three_d::Mat3::from_nonuniform_scale(
+            2.0 / viewport.width as f32,
+            -2.0 / viewport.height as f32,
         );
 
         self.draw_texture.use_vertex_attribute("a_uv", &a_uv);
         self.draw_texture.use_vertex_attribute("a_position", &geom);
-        self.draw_texture.use_uniform("u_matrix", matrix);
+        self.draw_texture.use_uniform("u_matrix", to_clip * matrix);
         self.draw_texture.use_texture("u_texture", texture);
+        let (wrap, border) = match sampling.wrap {
+            Wrap::Clamp => (0.0, [0.0; 4]),
+            Wrap::Repeat => (1.0, [0.0; 4]),
+            Wrap::MirroredRepeat => (2.0, [0.0; 4]),
+            Wrap::Border(color) => (3.0, color),
+        };
+        self.draw_texture
+            .use_uniform("u_filter", sampling.resampling.uniform());
+        self.draw_texture.use_uniform("u_wrap", wrap);
+        self.draw_texture
+            .use_uniform("u_border", three_d::Vec4::from(border));
         self.draw_texture.draw_arrays(
             three_d::RenderStates::default(),
             viewport,
@@ -116,37 +314,132 @@ impl Programs {
         texture2: &three_d::Texture2D,
         viewport: three_d::Viewport,
     ) {
-        let geom = three_d::VertexBuffer::new_with_data(
+        draw_two_textures(context, &self.blend_textures, texture1, texture2, viewport);
+    }
+
+    /// Visualize how `texture1` differs from `texture2`, with the uniforms of `difference.frag`
+    pub fn difference_textures(
+        &self,
+        context: &three_d::Context,
+        texture1: &three_d::Texture2D,
+        texture2: &three_d::Texture2D,
+        uniforms: &[(String, value::UniformValue)],
+        viewport: three_d::Viewport,
+    ) {
+        for (key, value) in uniforms.iter() {
+            if self.difference_textures.requires_uniform(key) {
+                value.apply(&self.difference_textures, key);
+            }
+        }
+        draw_two_textures(
             context,
-            &[
-                three_d::vec3(-1.0, -1.0, 0.0),
-                three_d::vec3(-1.0, 1.0, 0.0),
-                three_d::vec3(1.0, 1.0, 0.0),
-                three_d::vec3(-1.0, -1.0, 0.0),
-                three_d::vec3(1.0, 1.0, 0.0),
-                three_d::vec3(1.0, -1.0, 0.0),
-            ],
+            &self.difference_textures,
+            texture1,
+            texture2,
+            viewport,
         );
-        let a_uv = three_d::VertexBuffer::new_with_data(
+    }
+
+    /// Draw `texture` as seen with a color vision deficiency, simulated by `matrix` in linear light
+    pub fn color_blindness(
+        &self,
+        context: &three_d::Context,
+        texture: &three_d::Texture2D,
+        matrix: three_d::Mat3,
+        viewport: three_d::Viewport,
+    ) {
+        apply_shader(
             context,
-            &[
-                three_d::vec2(0.0, 0.0),
-                three_d::vec2(0.0, 1.0),
-                three_d::vec2(1.0, 1.0),
-                three_d::vec2(0.0, 0.0),
-                three_d::vec2(1.0, 1.0),
-                three_d::vec2(1.0, 0.0),
-            ],
-        );
-        self.blend_textures.use_vertex_attribute("a_uv", &a_uv);
-        self.blend_textures
-            .use_vertex_attribute("a_position", &geom);
-        self.blend_textures.use_texture("u_texture1", texture1);
-        self.blend_textures.use_texture("u_texture2", texture2);
-        self.blend_textures.draw_arrays(
-            three_d::RenderStates::default(),
+            &self.color_blindness,
+            &[("u_matrix".to_string(), value::UniformValue::Mat3(matrix))],
+            &[("u_texture", texture)],
             viewport,
-            geom.vertex_count(),
         );
     }
 }
+
+/// Draw a program reading `u_texture1` and `u_texture2` over the whole viewport
+fn draw_two_textures(
+    context: &three_d::Context,
+    program: &three_d::Program,
+    texture1: &three_d::Texture2D,
+    texture2: &three_d::Texture2D,
+    viewport: three_d::Viewport,
+) {
+    let (geom, a_uv) = fullscreen_quad(context);
+    program.use_vertex_attribute("a_uv", &a_uv);
+    program.use_vertex_attribute("a_position", &geom);
+    program.use_texture("u_texture1", texture1);
+    program.use_texture("u_texture2", texture2);
+    program.draw_arrays(
+        three_d::RenderStates::default(),
+        viewport,
+        geom.vertex_count(),
+    );
+}
+
+/// Vertex positions and UVs of two triangles covering the whole viewport
+pub fn fullscreen_quad(
+    context: &three_d::Context,
+) -> (three_d::VertexBuffer, three_d::VertexBuffer) {
+    let geom = three_d::VertexBuffer::new_with_data(
+        context,
+        &[
+            three_d::vec3(-1.0, -1.0, 0.0),
+            three_d::vec3(-1.0, 1.0, 0.0),
+            three_d::vec3(1.0, 1.0, 0.0),
+            three_d::vec3(-1.0, -1.0, 0.0),
+            three_d::vec3(1.0, 1.0, 0.0),
+            three_d::vec3(1.0, -1.0, 0.0),
+        ],
+    );
+    let a_uv = three_d::VertexBuffer::new_with_data(
+        context,
+        &[
+            three_d::vec2(0.0, 0.0),
+            three_d::vec2(0.0, 1.0),
+            three_d::vec2(1.0, 1.0),
+            three_d::vec2(0.0, 0.0),
+            three_d::vec2(1.0, 1.0),
+            three_d::vec2(1.0, 0.0),
+        ],
+    );
+    (geom, a_uv)
+}
+
+/// Run a full-screen pass of `program` into the current render target
+pub fn apply_shader(
+    context: &three_d::Context,
+    program: &three_d::Program,
+    uniforms: &[(String, value::UniformValue)],
+    textures: &[(&str, &three_d::Texture2D)],
+    viewport: three_d::Viewport,
+) {
+    let (geom, a_uv) = fullscreen_quad(context);
+    let u_resolution = three_d::vec2(viewport.width as f32, viewport.height as f32);
+
+    if program.requires_uniform("u_resolution") {
+        program.use_uniform("u_resolution", u_resolution);
+    }
+    for (key, value) in uniforms.iter() {
+        if program.requires_uniform(key) {
+            value.apply(program, key);
+        }
+    }
+    if program.requires_attribute("a_uv") {
+        program.use_vertex_attribute("a_uv", &a_uv);
+    }
+    if program.requires_attribute("a_position") {
+        program.use_vertex_attribute("a_position", &geom);
+    }
+    for (key, texture) in textures.iter() {
+        if program.requires_uniform(key) {
+            program.use_texture(key, texture);
+        }
+    }
+    program.draw_arrays(
+        three_d::RenderStates::default(),
+        viewport,
+        geom.vertex_count(),
+    );
+}
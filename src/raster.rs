@@ -0,0 +1,18 @@
+/// Convert a premultiplied pixmap to a texture with the straight alpha used by the compositor
+pub fn pixmap_to_texture(name: &str, pixmap: &tiny_skia::Pixmap) -> three_d::CpuTexture {
+    let data = pixmap
+        .pixels()
+        .iter()
+        .map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    three_d::CpuTexture {
+        name: name.to_string(),
+        data: three_d::TextureData::RgbaU8(data),
+        width: pixmap.width(),
+        height: pixmap.height(),
+        ..Default::default()
+    }
+}
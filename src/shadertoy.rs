@@ -1,13 +1,98 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use shadertoy::compare;
 use shadertoy::error::ShaderToyError;
+use shadertoy::palette;
 use shadertoy::ShaderToy;
 
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(long, required = true)]
+    input: Option<std::path::PathBuf>,
     #[arg(long)]
+    output: Option<std::path::PathBuf>,
+    /// Write the render for normal vision, protanopia, deuteranopia, tritanopia and
+    /// achromatopsia side by side to this PNG
+    #[arg(long)]
+    color_blindness: Option<std::path::PathBuf>,
+    /// Severity of the deficiencies simulated for `--color-blindness`, from 0 for normal vision
+    /// to 1 for the full deficiency
+    #[arg(long, default_value_t = 1.0, requires = "color_blindness")]
+    color_blindness_severity: f32,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two images, exiting with an error when they differ more than the thresholds
+    Compare(CompareArgs),
+    /// Print histograms and tonal statistics of a render or an image as JSON
+    Stats(StatsArgs),
+    /// Print the dominant colors of a render or an image as JSON
+    Palette(PaletteArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct PaletteArgs {
+    /// Composition JSON to render, or an image file
     input: std::path::PathBuf,
+    /// Number of colors
+    #[arg(long, default_value_t = 5)]
+    count: usize,
+    /// kmeans or median-cut
+    #[arg(long, default_value = "kmeans")]
+    method: palette::Method,
+    /// Write the colors as a row of swatches to this PNG
+    #[arg(long)]
+    swatch: Option<std::path::PathBuf>,
+    /// Render the composition again with the colors set as `vec4` uniforms, to this PNG
     #[arg(long)]
     output: Option<std::path::PathBuf>,
+    /// Name prefix of the uniforms set for `--output`
+    #[arg(long, default_value = "u_palette")]
+    uniform: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct StatsArgs {
+    /// Composition JSON to render, or an image file
+    input: std::path::PathBuf,
+    /// Analyze the result up to the node with this name instead of the final output
+    #[arg(long)]
+    node: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompareArgs {
+    /// Image to check, usually a render
+    actual: std::path::PathBuf,
+    /// Reference image
+    expected: std::path::PathBuf,
+    /// Channel differences up to this many levels out of 255 count as equal
+    #[arg(long, default_value_t = 1)]
+    tolerance: u8,
+    /// Largest allowed channel difference out of 255, past the tolerance
+    #[arg(long, default_value_t = 0.0)]
+    max_error: f64,
+    /// Largest allowed average channel difference out of 255, past the tolerance
+    #[arg(long)]
+    mean_error: Option<f64>,
+    /// Smallest allowed PSNR in decibels
+    #[arg(long)]
+    min_psnr: Option<f64>,
+    /// Smallest allowed SSIM, 1 for identical images
+    #[arg(long)]
+    min_ssim: Option<f64>,
+    /// Largest allowed average CIEDE2000 difference
+    #[arg(long)]
+    delta_e: Option<f64>,
+    /// Write a heatmap of the differences to this PNG
+    #[arg(long)]
+    diff: Option<std::path::PathBuf>,
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
 }
 
 #[tokio::main]
@@ -15,11 +100,24 @@ async fn main() -> Result<(), ShaderToyError> {
     env_logger::init();
 
     let args = Args::parse();
+    match args.command {
+        Some(Command::Compare(compare)) => return run_compare(compare),
+        Some(Command::Stats(stats)) => return run_stats(stats).await,
+        Some(Command::Palette(palette)) => return run_palette(palette).await,
+        None => (),
+    }
+    let input = args.input.expect("required by clap");
 
-    if let Some(output_path) = args.output {
+    if args.output.is_some() || args.color_blindness.is_some() {
         let context = three_d::HeadlessContext::new()?;
-        let mut maku = ShaderToy::load(&context, args.input).await?;
-        maku.render_to_file(&context, output_path)?;
+        let mut maku = ShaderToy::load(&context, input).await?;
+        if let Some(output_path) = args.output {
+            maku.render_to_file(&context, output_path)?;
+        }
+        if let Some(sheet_path) = args.color_blindness {
+            maku.color_blindness_sheet(&context, args.color_blindness_severity)?
+                .save_with_format(sheet_path, image::ImageFormat::Png)?;
+        }
     } else {
         let event_loop = winit::event_loop::EventLoop::new();
         let window = winit::window::WindowBuilder::new()
@@ -40,7 +138,7 @@ async fn main() -> Result<(), ShaderToyError> {
         )
         .unwrap();
 
-        let mut maku = ShaderToy::load(&context, args.input).await?;
+        let mut maku = ShaderToy::load(&context, input).await?;
         let mut frame_input_generator = three_d::FrameInputGenerator::from_winit_window(&window);
 
         event_loop.run(move |event, _, control_flow| {
@@ -68,7 +166,7 @@ async fn main() -> Result<(), ShaderToyError> {
                 }
                 winit::event::Event::RedrawRequested(_) => {
                     let frame_input = frame_input_generator.generate(&context);
-                    let mut target = maku::target::Target::Screen {
+                    let mut target = shadertoy::target::Target::Screen {
                         width: frame_input.viewport.width,
                         height: frame_input.viewport.height,
                     };
@@ -83,3 +181,97 @@ async fn main() -> Result<(), ShaderToyError> {
     }
     Ok(())
 }
+
+fn run_compare(args: CompareArgs) -> Result<(), ShaderToyError> {
+    let actual = compare::load(&args.actual)?;
+    let expected = compare::load(&args.expected)?;
+    let comparison = compare::compare(&actual, &expected, args.tolerance)?;
+    if let Some(diff) = args.diff {
+        compare::heatmap(&actual, &expected, args.tolerance)?.save(diff)?;
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&comparison)?);
+    } else {
+        println!(
+            "size:             {}x{}",
+            comparison.width, comparison.height
+        );
+        println!("max error:        {:.2}", comparison.max_error);
+        println!("mean error:       {:.4}", comparison.mean_error);
+        println!("differing pixels: {}", comparison.differing_pixels);
+        println!("PSNR:             {:.2} dB", comparison.psnr);
+        println!("SSIM:             {:.5}", comparison.ssim);
+        println!(
+            "ΔE:               {:.3} mean, {:.3} max",
+            comparison.mean_delta_e, comparison.max_delta_e
+        );
+    }
+
+    let failures = comparison.failures(&compare::Thresholds {
+        max_error: Some(args.max_error),
+        mean_error: args.mean_error,
+        min_psnr: args.min_psnr,
+        min_ssim: args.min_ssim,
+        delta_e: args.delta_e,
+    });
+    if !failures.is_empty() {
+        for failure in failures {
+            eprintln!("{}", failure);
+        }
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn run_stats(args: StatsArgs) -> Result<(), ShaderToyError> {
+    let stats = if is_composition(&args.input) {
+        let context = three_d::HeadlessContext::new()?;
+        let mut maku = ShaderToy::load(&context, args.input).await?;
+        maku.stats(&context, args.node.as_deref())?
+    } else if args.node.is_some() {
+        return Err(ShaderToyError::Usage(
+            "--node needs a composition JSON input".to_string(),
+        ));
+    } else {
+        shadertoy::stats::analyze(&image::open(&args.input)?.into_rgba8())
+    };
+    println!("{}", serde_json::to_string_pretty(&stats)?);
+    Ok(())
+}
+
+async fn run_palette(args: PaletteArgs) -> Result<(), ShaderToyError> {
+    let colors = if is_composition(&args.input) {
+        let context = three_d::HeadlessContext::new()?;
+        let mut maku = ShaderToy::load(&context, args.input).await?;
+        let colors = maku.palette(&context, args.count, args.method)?;
+        if let Some(output_path) = args.output {
+            maku.set_palette(&args.uniform, &colors);
+            maku.render_to_file(&context, output_path)?;
+        }
+        colors
+    } else if args.output.is_some() {
+        return Err(ShaderToyError::Usage(
+            "--output needs a composition JSON input".to_string(),
+        ));
+    } else {
+        palette::extract(
+            &image::open(&args.input)?.into_rgba8(),
+            args.count,
+            args.method,
+        )
+    };
+
+    if let Some(swatch) = args.swatch {
+        palette::swatch(&colors, 64 * colors.len().max(1) as u32, 64).save(swatch)?;
+    }
+    println!("{}", serde_json::to_string_pretty(&colors)?);
+    Ok(())
+}
+
+/// Whether an input is a composition JSON to render, rather than an image file
+fn is_composition(input: &std::path::Path) -> bool {
+    input
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
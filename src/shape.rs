@@ -0,0 +1,295 @@
+use crate::error::ShaderToyError;
+use crate::io;
+use crate::raster;
+
+/// Rasterize a shape node into a texture of the composition size, centered on the layer origin
+pub fn rasterize(
+    shape: &io::IoShape,
+    width: u32,
+    height: u32,
+) -> Result<three_d::CpuTexture, ShaderToyError> {
+    let path = build_path(&shape.geometry)?;
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or_else(|| {
+        ShaderToyError::InvalidNode(format!("invalid shape size: {}x{}", width, height))
+    })?;
+    let transform = tiny_skia::Transform::from_translate(width as f32 * 0.5, height as f32 * 0.5);
+
+    if let Some(fill) = &shape.fill {
+        let paint = load_paint(fill, shape.anti_alias)?;
+        pixmap.fill_path(&path, &paint, tiny_skia::FillRule::Winding, transform, None);
+    }
+
+    if let Some(stroke) = &shape.stroke {
+        let paint = load_paint(&stroke.paint, shape.anti_alias)?;
+        let dash = dash_array(&stroke.dash);
+        let dash = if dash.is_empty() {
+            None
+        } else {
+            Some(
+                tiny_skia::StrokeDash::new(dash, stroke.dash_offset).ok_or_else(|| {
+                    ShaderToyError::InvalidNode("invalid stroke dash".to_string())
+                })?,
+            )
+        };
+        let stroke = tiny_skia::Stroke {
+            width: stroke.width,
+            line_join: match stroke.join {
+                io::LineJoin::Miter => tiny_skia::LineJoin::Miter,
+                io::LineJoin::Round => tiny_skia::LineJoin::Round,
+                io::LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
+            },
+            line_cap: match stroke.cap {
+                io::LineCap::Butt => tiny_skia::LineCap::Butt,
+                io::LineCap::Round => tiny_skia::LineCap::Round,
+                io::LineCap::Square => tiny_skia::LineCap::Square,
+            },
+            dash,
+            ..Default::default()
+        };
+        pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+    }
+
+    Ok(raster::pixmap_to_texture("shape", &pixmap))
+}
+
+fn build_path(geometry: &io::IoGeometry) -> Result<tiny_skia::Path, ShaderToyError> {
+    let mut pb = tiny_skia::PathBuilder::new();
+    match geometry {
+        io::IoGeometry::Rect {
+            width,
+            height,
+            radii,
+        } => {
+            let (x0, y0, x1, y1) = (-width * 0.5, -height * 0.5, width * 0.5, height * 0.5);
+            let max_radius = width.min(*height) * 0.5;
+            let [tl, tr, br, bl] = radii.map(|r| r.clamp(0.0, max_radius));
+            pb.move_to(x0 + tl, y0);
+            pb.line_to(x1 - tr, y0);
+            corner(&mut pb, (x1 - tr, y0), (x1, y0), (x1, y0 + tr));
+            pb.line_to(x1, y1 - br);
+            corner(&mut pb, (x1, y1 - br), (x1, y1), (x1 - br, y1));
+            pb.line_to(x0 + bl, y1);
+            corner(&mut pb, (x0 + bl, y1), (x0, y1), (x0, y1 - bl));
+            pb.line_to(x0, y0 + tl);
+            corner(&mut pb, (x0, y0 + tl), (x0, y0), (x0 + tl, y0));
+            pb.close();
+        }
+        io::IoGeometry::Ellipse { width, height } => {
+            if let Some(rect) =
+                tiny_skia::Rect::from_xywh(-width * 0.5, -height * 0.5, *width, *height)
+            {
+                pb.push_oval(rect);
+            }
+        }
+        io::IoGeometry::Polygon { radius, sides } => {
+            let vertices = (0..*sides).map(|i| (*radius, i as f32 / *sides as f32));
+            polygon(&mut pb, vertices);
+        }
+        io::IoGeometry::Star {
+            outer_radius,
+            inner_radius,
+            points,
+        } => {
+            let count = points * 2;
+            let vertices = (0..count).map(|i| {
+                let radius = if i % 2 == 0 {
+                    *outer_radius
+                } else {
+                    *inner_radius
+                };
+                (radius, i as f32 / count as f32)
+            });
+            polygon(&mut pb, vertices);
+        }
+        io::IoGeometry::Line { from, to } => {
+            pb.move_to(from[0], from[1]);
+            pb.line_to(to[0], to[1]);
+        }
+        io::IoGeometry::Path { d } => {
+            for segment in svgtypes::SimplifyingPathParser::from(d.as_str()) {
+                let segment = segment.map_err(|e| {
+                    ShaderToyError::InvalidNode(format!("invalid path data: {}", e))
+                })?;
+                match segment {
+                    svgtypes::SimplePathSegment::MoveTo { x, y } => pb.move_to(x as f32, y as f32),
+                    svgtypes::SimplePathSegment::LineTo { x, y } => pb.line_to(x as f32, y as f32),
+                    svgtypes::SimplePathSegment::CurveTo {
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        x,
+                        y,
+                    } => pb.cubic_to(
+                        x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32,
+                    ),
+                    svgtypes::SimplePathSegment::Quadratic { x1, y1, x, y } => {
+                        pb.quad_to(x1 as f32, y1 as f32, x as f32, y as f32)
+                    }
+                    svgtypes::SimplePathSegment::ClosePath => pb.close(),
+                }
+            }
+        }
+    }
+    pb.finish()
+        .ok_or_else(|| ShaderToyError::InvalidNode("empty shape geometry".to_string()))
+}
+
+/// Like SVG, an odd dash list is repeated to make it even
+fn dash_array(dash: &[f32]) -> Vec<f32> {
+    if dash.len() % 2 == 1 {
+        dash.repeat(2)
+    } else {
+        dash.to_vec()
+    }
+}
+
+/// Quarter-ellipse corner from `start` to `end`, bulging towards `corner`
+fn corner(pb: &mut tiny_skia::PathBuilder, start: (f32, f32), corner: (f32, f32), end: (f32, f32)) {
+    // Control point distance approximating a circular arc with a cubic
+    const KAPPA: f32 = 0.552_284_8;
+    pb.cubic_to(
+        start.0 + (corner.0 - start.0) * KAPPA,
+        start.1 + (corner.1 - start.1) * KAPPA,
+        end.0 + (corner.0 - end.0) * KAPPA,
+        end.1 + (corner.1 - end.1) * KAPPA,
+        end.0,
+        end.1,
+    );
+}
+
+/// Closed polygon through `(radius, turn)` vertices, turns starting at the top going clockwise
+fn polygon(pb: &mut tiny_skia::PathBuilder, vertices: impl Iterator<Item = (f32, f32)>) {
+    for (i, (radius, turn)) in vertices.enumerate() {
+        let (sin, cos) = (turn * std::f32::consts::TAU).sin_cos();
+        let (x, y) = (radius * sin, -radius * cos);
+        if i == 0 {
+            pb.move_to(x, y);
+        } else {
+            pb.line_to(x, y);
+        }
+    }
+    pb.close();
+}
+
+fn load_paint(
+    paint: &io::IoPaint,
+    anti_alias: bool,
+) -> Result<tiny_skia::Paint<'static>, ShaderToyError> {
+    let shader = match paint {
+        io::IoPaint::Solid { color } => tiny_skia::Shader::SolidColor(color_from(*color)),
+        io::IoPaint::Linear { stops, start, end } => tiny_skia::LinearGradient::new(
+            tiny_skia::Point::from_xy(start[0], start[1]),
+            tiny_skia::Point::from_xy(end[0], end[1]),
+            gradient_stops(stops)?,
+            tiny_skia::SpreadMode::Pad,
+            tiny_skia::Transform::identity(),
+        )
+        .ok_or_else(|| ShaderToyError::InvalidNode("invalid linear gradient".to_string()))?,
+        io::IoPaint::Radial {
+            stops,
+            center,
+            radius,
+        } => {
+            let center = tiny_skia::Point::from_xy(center[0], center[1]);
+            tiny_skia::RadialGradient::new(
+                center,
+                center,
+                *radius,
+                gradient_stops(stops)?,
+                tiny_skia::SpreadMode::Pad,
+                tiny_skia::Transform::identity(),
+            )
+            .ok_or_else(|| ShaderToyError::InvalidNode("invalid radial gradient".to_string()))?
+        }
+    };
+    Ok(tiny_skia::Paint {
+        shader,
+        anti_alias,
+        ..Default::default()
+    })
+}
+
+pub(crate) fn gradient_stops(
+    stops: &[io::IoGradientStop],
+) -> Result<Vec<tiny_skia::GradientStop>, ShaderToyError> {
+    if stops.is_empty() {
+        return Err(ShaderToyError::InvalidNode(
+            "gradients need at least one stop".to_string(),
+        ));
+    }
+    Ok(stops
+        .iter()
+        .map(|stop| tiny_skia::GradientStop::new(stop.offset, color_from(stop.color)))
+        .collect())
+}
+
+fn color_from(color: [f32; 4]) -> tiny_skia::Color {
+    let [r, g, b, a] = color.map(|c| c.clamp(0.0, 1.0));
+    tiny_skia::Color::from_rgba(r, g, b, a).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(json: &str) -> [f32; 4] {
+        let path = build_path(&serde_json::from_str(json).unwrap()).unwrap();
+        let bounds = path.bounds();
+        [bounds.left(), bounds.top(), bounds.right(), bounds.bottom()]
+    }
+
+    fn assert_near(actual: [f32; 4], expected: [f32; 4]) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(a, e)| (a - e).abs() < 1e-3),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn geometry_is_centered_on_the_origin() {
+        assert_near(
+            bounds(r#"{"type": "rect", "width": 40, "height": 20}"#),
+            [-20.0, -10.0, 20.0, 10.0],
+        );
+        // Radii past half the smallest side are clamped, keeping the box
+        assert_near(
+            bounds(r#"{"type": "rect", "width": 40, "height": 20, "radii": [50, 50, 50, 50]}"#),
+            [-20.0, -10.0, 20.0, 10.0],
+        );
+        assert_near(
+            bounds(r#"{"type": "ellipse", "width": 30, "height": 10}"#),
+            [-15.0, -5.0, 15.0, 5.0],
+        );
+        // A square's first vertex is at the top
+        assert_near(
+            bounds(r#"{"type": "polygon", "radius": 10, "sides": 4}"#),
+            [-10.0, -10.0, 10.0, 10.0],
+        );
+        assert_near(
+            bounds(r#"{"type": "path", "d": "M 0 0 H 10 V 5 h -20 z"}"#),
+            [-10.0, 0.0, 10.0, 5.0],
+        );
+    }
+
+    #[test]
+    fn invalid_path_data_is_an_error() {
+        for d in ["M 0 0 L 10", "M 0 0 X 5 5", ""] {
+            let geometry = serde_json::from_str(&format!(r#"{{"type": "path", "d": "{}"}}"#, d));
+            assert!(build_path(&geometry.unwrap()).is_err(), "{}", d);
+        }
+    }
+
+    #[test]
+    fn odd_dash_arrays_are_doubled() {
+        assert_eq!(dash_array(&[4.0]), [4.0, 4.0]);
+        assert_eq!(dash_array(&[4.0, 2.0, 1.0]), [4.0, 2.0, 1.0, 4.0, 2.0, 1.0]);
+        assert_eq!(dash_array(&[4.0, 2.0]), [4.0, 2.0]);
+        assert!(dash_array(&[]).is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+
+use crate::color;
+
+/// Tonal statistics of an image. Color channels only count visible pixels, whose alpha is not
+/// zero, as fully transparent pixels have no meaningful color.
+#[derive(Clone, Debug, Serialize)]
+pub struct Stats {
+    pub width: u32,
+    pub height: u32,
+    pub red: ChannelStats,
+    pub green: ChannelStats,
+    pub blue: ChannelStats,
+    /// Rec. 709 luminance, as used by `BlackWhite`
+    pub luminance: ChannelStats,
+    /// Over all pixels
+    pub alpha: ChannelStats,
+    /// Percentage of visible pixels with a color channel at 0
+    pub clipped_shadows: f64,
+    /// Percentage of visible pixels with a color channel at 255
+    pub clipped_highlights: f64,
+    /// Percentage of pixels which are not fully transparent
+    pub alpha_coverage: f64,
+    /// Percentage of fully opaque pixels
+    pub opaque: f64,
+}
+
+/// Statistics of one channel, in levels from 0 to 255
+#[derive(Clone, Debug, Serialize)]
+pub struct ChannelStats {
+    pub min: u8,
+    pub max: u8,
+    pub mean: f64,
+    pub median: u8,
+    pub stddev: f64,
+    /// Percentage of counted pixels at 0
+    pub clipped_low: f64,
+    /// Percentage of counted pixels at 255
+    pub clipped_high: f64,
+    /// Number of pixels at each level
+    pub histogram: Vec<u64>,
+}
+
+/// Compute the statistics of an RGBA image
+pub fn analyze(image: &image::RgbaImage) -> Stats {
+    let mut histograms = [[0u64; 256]; 5];
+    let (mut shadows, mut highlights, mut visible, mut opaque) = (0u64, 0u64, 0u64, 0u64);
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        histograms[4][a as usize] += 1;
+        opaque += (a == 255) as u64;
+        if a == 0 {
+            continue;
+        }
+        visible += 1;
+        let luminance = color::luminance([r, g, b].map(f64::from));
+        for (histogram, value) in histograms
+            .iter_mut()
+            .zip([r, g, b, luminance.round() as u8])
+        {
+            histogram[value as usize] += 1;
+        }
+        shadows += (r == 0 || g == 0 || b == 0) as u64;
+        highlights += (r == 255 || g == 255 || b == 255) as u64;
+    }
+
+    let total = image.width() as u64 * image.height() as u64;
+    let [red, green, blue, luminance, alpha] = histograms.map(|histogram| channel(&histogram));
+    Stats {
+        width: image.width(),
+        height: image.height(),
+        red,
+        green,
+        blue,
+        luminance,
+        alpha,
+        clipped_shadows: percentage(shadows, visible),
+        clipped_highlights: percentage(highlights, visible),
+        alpha_coverage: percentage(visible, total),
+        opaque: percentage(opaque, total),
+    }
+}
+
+fn channel(histogram: &[u64; 256]) -> ChannelStats {
+    let count: u64 = histogram.iter().sum();
+    let levels = || (0..=255u8).zip(histogram.iter().copied());
+    let present = || levels().filter(|(_, n)| *n > 0).map(|(level, _)| level);
+
+    let mean = levels()
+        .map(|(level, n)| level as f64 * n as f64)
+        .sum::<f64>()
+        / count.max(1) as f64;
+    let variance = levels()
+        .map(|(level, n)| (level as f64 - mean).powi(2) * n as f64)
+        .sum::<f64>()
+        / count.max(1) as f64;
+    // Lowest level reaching half of the pixels
+    let mut seen = 0;
+    let median = levels()
+        .find(|(_, n)| {
+            seen += n;
+            seen * 2 >= count
+        })
+        .map_or(0, |(level, _)| level);
+
+    ChannelStats {
+        min: present().next().unwrap_or(0),
+        max: present().next_back().unwrap_or(0),
+        mean,
+        median,
+        stddev: variance.sqrt(),
+        clipped_low: percentage(histogram[0], count),
+        clipped_high: percentage(histogram[255], count),
+        histogram: histogram.to_vec(),
+    }
+}
+
+fn percentage(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(levels: &[(u8, u64)]) -> [u64; 256] {
+        let mut histogram = [0; 256];
+        for (level, n) in levels {
+            histogram[*level as usize] = *n;
+        }
+        histogram
+    }
+
+    #[test]
+    fn channel_median_is_the_lowest_level_reaching_half() {
+        assert_eq!(
+            channel(&histogram(&[(10, 1), (20, 1), (200, 1)])).median,
+            20
+        );
+        assert_eq!(channel(&histogram(&[(10, 2), (20, 2)])).median, 10);
+        assert_eq!(channel(&histogram(&[(10, 1), (20, 5)])).median, 20);
+    }
+
+    #[test]
+    fn channel_moments_and_clipping() {
+        let stats = channel(&histogram(&[(0, 1), (100, 2), (255, 1)]));
+        assert_eq!((stats.min, stats.max), (0, 255));
+        assert!((stats.mean - 113.75).abs() < 1e-9);
+        assert_eq!(stats.clipped_low, 25.0);
+        assert_eq!(stats.clipped_high, 25.0);
+    }
+
+    #[test]
+    fn empty_channel() {
+        let stats = channel(&[0; 256]);
+        assert_eq!((stats.min, stats.max, stats.median), (0, 0, 0));
+        assert_eq!((stats.mean, stats.clipped_low), (0.0, 0.0));
+    }
+
+    #[test]
+    fn transparent_pixels_only_count_for_alpha() {
+        let mut image = image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 255, 255, 0]));
+        image.put_pixel(0, 0, image::Rgba([0, 128, 64, 255]));
+        image.put_pixel(1, 0, image::Rgba([255, 128, 64, 128]));
+        let stats = analyze(&image);
+
+        assert_eq!(stats.red.histogram.iter().sum::<u64>(), 2);
+        assert_eq!(stats.green.histogram[128], 2);
+        assert_eq!(stats.alpha.histogram.iter().sum::<u64>(), 4);
+        assert_eq!(stats.alpha.histogram[0], 2);
+        assert_eq!(stats.clipped_shadows, 50.0);
+        assert_eq!(stats.clipped_highlights, 50.0);
+        assert_eq!(stats.alpha_coverage, 50.0);
+        assert_eq!(stats.opaque, 25.0);
+    }
+}
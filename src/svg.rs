@@ -0,0 +1,157 @@
+use crate::error::ShaderToyError;
+use crate::io;
+use crate::raster;
+
+/// Rasterize an SVG image at the size it will be drawn, including its fit into `canvas` when
+/// given and its transform's scale, so it is never upscaled afterwards.
+/// Returns the texture and its resolution relative to the drawn size, per axis.
+pub fn rasterize(
+    data: &[u8],
+    image: &io::IoImage,
+    canvas: Option<[f32; 2]>,
+    resources_dir: &std::path::Path,
+) -> Result<(three_d::CpuTexture, [f32; 2]), ShaderToyError> {
+    let mut options = resvg::usvg::Options {
+        resources_dir: Some(resources_dir.to_path_buf()),
+        ..Default::default()
+    };
+    options.fontdb_mut().load_system_fonts();
+    let tree = resvg::usvg::Tree::from_data(data, &options)?;
+
+    // Keep the aspect ratio when only one dimension is overridden
+    let intrinsic = tree.size();
+    let (width, height) = match (image.width, image.height) {
+        (Some(w), Some(h)) => (w, h),
+        (Some(w), None) => (w, w * intrinsic.height() / intrinsic.width()),
+        (None, Some(h)) => (h * intrinsic.width() / intrinsic.height(), h),
+        (None, None) => (intrinsic.width(), intrinsic.height()),
+    };
+
+    // The fitted part is the crop when there is one, empty ones being rejected when placed
+    let [crop_width, crop_height] = image
+        .source_rect
+        .map_or([width, height], |rect| [rect[2], rect[3]]);
+    let fit = match canvas {
+        Some(canvas) if crop_width > 0.0 && crop_height > 0.0 => {
+            let fitted = image.fit.size([crop_width, crop_height], canvas);
+            [fitted[0] / crop_width, fitted[1] / crop_height]
+        }
+        _ => [1.0, 1.0],
+    };
+    let scale = image
+        .transform
+        .scale()
+        .map(|s| if s == 0.0 { 1.0 } else { s });
+    let pixel_width = (width * fit[0] * scale[0]).ceil().max(1.0);
+    let pixel_height = (height * fit[1] * scale[1]).ceil().max(1.0);
+
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(pixel_width as u32, pixel_height as u32)
+        .ok_or_else(|| {
+            ShaderToyError::InvalidNode(format!(
+                "invalid SVG size: {}x{}",
+                pixel_width, pixel_height
+            ))
+        })?;
+    if let Some(background) = image.background {
+        let [r, g, b, a] = background.map(|c| c.clamp(0.0, 1.0));
+        pixmap.fill(resvg::tiny_skia::Color::from_rgba(r, g, b, a).unwrap());
+    }
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(
+            pixel_width / intrinsic.width(),
+            pixel_height / intrinsic.height(),
+        ),
+        &mut pixmap.as_mut(),
+    );
+
+    Ok((
+        raster::pixmap_to_texture(&image.path, &pixmap),
+        [pixel_width / width, pixel_height / height],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10×5 rectangle
+    const SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="5" viewBox="0 0 10 5"><rect width="10" height="5" fill="red"/></svg>"#;
+
+    fn image(json: &str) -> io::IoImage {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn rasterize_svg(image: &io::IoImage, canvas: Option<[f32; 2]>) -> (u32, u32, [f32; 2]) {
+        let (texture, resolution) =
+            rasterize(SVG.as_bytes(), image, canvas, std::path::Path::new(".")).unwrap();
+        (texture.width, texture.height, resolution)
+    }
+
+    #[test]
+    fn fitted_svg_is_rasterized_at_the_box_size() {
+        let contain = image(r#"{"path": "logo.svg", "fit": "contain"}"#);
+        assert_eq!(
+            rasterize_svg(&contain, Some([200.0, 300.0])),
+            (200, 100, [20.0, 20.0])
+        );
+        let fill = image(r#"{"path": "logo.svg", "fit": "fill"}"#);
+        assert_eq!(
+            rasterize_svg(&fill, Some([200.0, 300.0])),
+            (200, 300, [20.0, 60.0])
+        );
+    }
+
+    #[test]
+    fn unfitted_svg_is_rasterized_at_its_size() {
+        let none = image(r#"{"path": "logo.svg"}"#);
+        assert_eq!(
+            rasterize_svg(&none, Some([200.0, 300.0])),
+            (10, 5, [1.0, 1.0])
+        );
+        let source = image(r#"{"path": "logo.svg", "fit": "contain"}"#);
+        assert_eq!(rasterize_svg(&source, None), (10, 5, [1.0, 1.0]));
+    }
+
+    #[test]
+    fn one_dimension_keeps_the_view_box_aspect_ratio() {
+        // No width or height, the size comes from the 30×10 viewBox
+        let view_box = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 30 10"><rect width="30" height="10"/></svg>"#;
+        for (json, size) in [
+            (r#"{"path": "logo.svg", "width": 60}"#, (60, 20)),
+            (r#"{"path": "logo.svg", "height": 40}"#, (120, 40)),
+            (
+                r#"{"path": "logo.svg", "width": 60, "height": 60}"#,
+                (60, 60),
+            ),
+        ] {
+            let (texture, _) = rasterize(
+                view_box.as_bytes(),
+                &image(json),
+                None,
+                std::path::Path::new("."),
+            )
+            .unwrap();
+            assert_eq!((texture.width, texture.height), size, "{}", json);
+        }
+        let width_only = image(r#"{"path": "logo.svg", "width": 30}"#);
+        assert_eq!(rasterize_svg(&width_only, None), (30, 15, [1.0, 1.0]));
+    }
+
+    #[test]
+    fn malformed_svg_is_an_error() {
+        let image = image(r#"{"path": "logo.svg"}"#);
+        for data in [
+            "",
+            "not an svg",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\"",
+            "<html></html>",
+        ] {
+            assert!(
+                rasterize(data.as_bytes(), &image, None, std::path::Path::new(".")).is_err(),
+                "{}",
+                data
+            );
+        }
+    }
+}
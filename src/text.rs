@@ -0,0 +1,258 @@
+use rustybuzz::ttf_parser;
+
+use crate::error::ShaderToyError;
+use crate::io;
+use crate::raster;
+
+/// Shape and rasterize a text node into a texture sized to fit the laid out text
+pub fn rasterize(
+    font_data: &[u8],
+    text: &io::IoText,
+) -> Result<three_d::CpuTexture, ShaderToyError> {
+    let face = rustybuzz::Face::from_slice(font_data, 0)
+        .ok_or_else(|| ShaderToyError::InvalidNode(format!("unreadable font: {}", text.font)))?;
+
+    let scale = text.size / face.units_per_em() as f32;
+    let ascender = face.ascender() as f32 * scale;
+    let descender = face.descender() as f32 * scale;
+    let line_advance = text.size * text.line_height;
+    // Room for glyphs overhanging their advance, such as italics
+    let padding = (text.size * 0.1).ceil() + 1.0;
+
+    let lines = wrap(&face, text, scale);
+    let box_width = text
+        .max_width
+        .unwrap_or_else(|| lines.iter().map(|(_, w)| *w).fold(0.0, f32::max));
+    let width = (box_width + 2.0 * padding).ceil().max(1.0) as u32;
+    let height = ((lines.len() - 1) as f32 * line_advance + ascender - descender + 2.0 * padding)
+        .ceil()
+        .max(1.0) as u32;
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or_else(|| {
+        ShaderToyError::InvalidNode(format!("text is too large: {}x{}", width, height))
+    })?;
+    let [r, g, b, a] = text.color.map(|c| c.clamp(0.0, 1.0));
+    let mut paint = tiny_skia::Paint::default();
+    paint.set_color(tiny_skia::Color::from_rgba(r, g, b, a).unwrap());
+
+    for (i, (line, line_width)) in lines.iter().enumerate() {
+        let baseline = padding + ascender + i as f32 * line_advance;
+        let mut x = padding
+            + match text.align {
+                io::TextAlign::Left => 0.0,
+                io::TextAlign::Center => (box_width - line_width) * 0.5,
+                io::TextAlign::Right => box_width - line_width,
+            };
+
+        let glyphs = shape(&face, line);
+        for (i, (info, position)) in glyphs
+            .glyph_infos()
+            .iter()
+            .zip(glyphs.glyph_positions())
+            .enumerate()
+        {
+            // Spacing goes between glyphs only, like in `measure`, so alignment is exact
+            if i > 0 {
+                x += text.letter_spacing;
+            }
+            let mut outline = Outline {
+                builder: tiny_skia::PathBuilder::new(),
+                x: x + position.x_offset as f32 * scale,
+                y: baseline - position.y_offset as f32 * scale,
+                scale,
+            };
+            let glyph_id = ttf_parser::GlyphId(info.glyph_id as u16);
+            if face.outline_glyph(glyph_id, &mut outline).is_some() {
+                if let Some(path) = outline.builder.finish() {
+                    pixmap.fill_path(
+                        &path,
+                        &paint,
+                        tiny_skia::FillRule::Winding,
+                        tiny_skia::Transform::identity(),
+                        None,
+                    );
+                }
+            }
+            x += position.x_advance as f32 * scale;
+        }
+    }
+
+    Ok(raster::pixmap_to_texture(&text.text, &pixmap))
+}
+
+fn shape(face: &rustybuzz::Face, text: &str) -> rustybuzz::GlyphBuffer {
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    rustybuzz::shape(face, &[], buffer)
+}
+
+/// Width in pixels of a shaped line
+fn measure(face: &rustybuzz::Face, line: &str, scale: f32, letter_spacing: f32) -> f32 {
+    let glyphs = shape(face, line);
+    let advance: i32 = glyphs.glyph_positions().iter().map(|p| p.x_advance).sum();
+    advance as f32 * scale + letter_spacing * glyphs.len().saturating_sub(1) as f32
+}
+
+/// Break the text into lines on newlines, and between words past `max_width`
+fn wrap(face: &rustybuzz::Face, text: &io::IoText, scale: f32) -> Vec<(String, f32)> {
+    let measure = |line: &str| measure(face, line, scale, text.letter_spacing);
+    let mut lines = vec![];
+    for paragraph in text.text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            let overflows = text
+                .max_width
+                .is_some_and(|max_width| measure(&candidate) > max_width);
+            if overflows && !line.is_empty() {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+        .into_iter()
+        .map(|line| {
+            let width = measure(&line);
+            (line, width)
+        })
+        .collect()
+}
+
+/// Glyph outline in font units, placed at `x`/`y` in pixels with a top-left origin
+struct Outline {
+    builder: tiny_skia::PathBuilder,
+    x: f32,
+    y: f32,
+    scale: f32,
+}
+
+impl Outline {
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.x + x * self.scale, self.y - y * self.scale)
+    }
+}
+
+impl ttf_parser::OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.builder.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.point(x, y);
+        self.builder.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x, y) = self.point(x, y);
+        self.builder.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x2, y2) = self.point(x2, y2);
+        let (x, y) = self.point(x, y);
+        self.builder.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.builder.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &[u8] = include_bytes!("../tests/fonts/Tuffy.ttf");
+
+    fn text(json: &str) -> io::IoText {
+        let mut text: io::IoText = serde_json::from_str(json).unwrap();
+        text.font = "Tuffy.ttf".to_string();
+        text
+    }
+
+    fn lines(text: &io::IoText) -> Vec<String> {
+        let face = rustybuzz::Face::from_slice(FONT, 0).unwrap();
+        let scale = text.size / face.units_per_em() as f32;
+        wrap(&face, text, scale)
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect()
+    }
+
+    /// Leftmost and rightmost columns with ink
+    fn ink_columns(text: &io::IoText) -> (u32, u32) {
+        let texture = rasterize(FONT, text).unwrap();
+        let three_d::TextureData::RgbaU8(data) = &texture.data else {
+            panic!("text is 8 bit RGBA");
+        };
+        let inked: Vec<u32> = (0..texture.width)
+            .filter(|x| (0..texture.height).any(|y| data[(y * texture.width + x) as usize][3] > 0))
+            .collect();
+        (inked[0], inked[inked.len() - 1])
+    }
+
+    #[test]
+    fn spacing_goes_between_glyphs_only() {
+        let face = rustybuzz::Face::from_slice(FONT, 0).unwrap();
+        let plain = measure(&face, "abc", 0.1, 0.0);
+        assert!((measure(&face, "abc", 0.1, 5.0) - plain - 10.0).abs() < 1e-4);
+        assert_eq!(measure(&face, "a", 0.1, 5.0), measure(&face, "a", 0.1, 0.0));
+    }
+
+    #[test]
+    fn lines_break_at_max_width() {
+        let face = rustybuzz::Face::from_slice(FONT, 0).unwrap();
+        let scale = 32.0 / face.units_per_em() as f32;
+        let max_width = measure(&face, "one two", scale, 3.0) + 0.5;
+        let text = text(&format!(
+            r#"{{"text": "one two three\nfour", "font": "", "size": 32, "letter_spacing": 3, "max_width": {}}}"#,
+            max_width
+        ));
+        assert_eq!(lines(&text), ["one two", "three", "four"]);
+    }
+
+    #[test]
+    fn overlong_words_keep_their_own_line() {
+        let words = text(
+            r#"{"text": "a incomprehensibilities b", "font": "", "size": 32, "max_width": 10}"#,
+        );
+        assert_eq!(lines(&words), ["a", "incomprehensibilities", "b"]);
+        let word =
+            text(r#"{"text": "incomprehensibilities", "font": "", "size": 32, "max_width": 10}"#);
+        assert_eq!(lines(&word), ["incomprehensibilities"]);
+    }
+
+    #[test]
+    fn alignment_ignores_trailing_spacing() {
+        let ink = |align: &str, letter_spacing: f32| {
+            ink_columns(&text(&format!(
+                r#"{{"text": "HIH", "font": "", "size": 32, "max_width": 300, "align": "{}", "letter_spacing": {}}}"#,
+                align, letter_spacing
+            )))
+        };
+        // The last glyph ends at the same place however far apart glyphs are
+        let (_, right) = ink("right", 0.0);
+        let (_, spaced_right) = ink("right", 20.0);
+        assert!(
+            right.abs_diff(spaced_right) <= 1,
+            "{} != {}",
+            right,
+            spaced_right
+        );
+        // Spacing spreads evenly around the center
+        let (left, right) = ink("center", 0.0);
+        let (spaced_left, spaced_right) = ink("center", 20.0);
+        assert!((left + right).abs_diff(spaced_left + spaced_right) <= 1);
+        assert!(spaced_right - spaced_left >= right - left + 39);
+    }
+}
@@ -1,9 +1,12 @@
+#[derive(Clone)]
 pub enum UniformValue {
     Float(f32),
     Vec2(three_d::Vec2),
     Vec3(three_d::Vec3),
     Vec4(three_d::Vec4),
     Mat3(three_d::Mat3),
+    FloatArray(Vec<f32>),
+    Vec4Array(Vec<three_d::Vec4>),
 }
 
 impl UniformValue {
@@ -14,6 +17,8 @@ impl UniformValue {
             UniformValue::Vec3(v) => program.use_uniform(name, v),
             UniformValue::Vec4(v) => program.use_uniform(name, v),
             UniformValue::Mat3(v) => program.use_uniform(name, v),
+            UniformValue::FloatArray(v) => program.use_uniform_array(name, v),
+            UniformValue::Vec4Array(v) => program.use_uniform_array(name, v),
         };
     }
 }
@@ -43,3 +48,15 @@ impl From<[f32; 4]> for UniformValue {
         ))
     }
 }
+
+impl From<Vec<f32>> for UniformValue {
+    fn from(value: Vec<f32>) -> Self {
+        Self::FloatArray(value)
+    }
+}
+
+impl From<Vec<[f32; 4]>> for UniformValue {
+    fn from(value: Vec<[f32; 4]>) -> Self {
+        Self::Vec4Array(value.into_iter().map(three_d::Vector4::from).collect())
+    }
+}
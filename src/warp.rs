@@ -0,0 +1,243 @@
+use three_d::SquareMatrix;
+
+use crate::error::ShaderToyError;
+use crate::io;
+use crate::programs;
+
+/// How a warped layer is drawn: through a projective matrix, or as a mesh with the identity
+pub enum Warp {
+    Matrix(three_d::Mat3),
+    Mesh(programs::Mesh),
+}
+
+/// Build the warp of a layer drawn in `bounds` (center x, center y, width and height in pixels
+/// from the canvas center) whose texture coordinates span `uv` (left, top, right, bottom)
+pub fn load(
+    warp: &io::IoWarp,
+    bounds: [f32; 4],
+    uv: [f32; 4],
+    canvas: [f32; 2],
+) -> Result<Warp, ShaderToyError> {
+    match warp {
+        io::IoWarp::CornerPin { corners } => corner_pin(corners, bounds, canvas).map(Warp::Matrix),
+        io::IoWarp::Mesh {
+            columns,
+            rows,
+            points,
+            subdivisions,
+        } => mesh(*columns, *rows, points, *subdivisions, uv, canvas).map(Warp::Mesh),
+    }
+}
+
+/// Homography taking the corners of `bounds` to `corners`, given from the canvas's top-left
+fn corner_pin(
+    corners: &[[f32; 2]; 4],
+    bounds: [f32; 4],
+    canvas: [f32; 2],
+) -> Result<three_d::Mat3, ShaderToyError> {
+    let [center_x, center_y, width, height] = bounds;
+    let degenerate = || ShaderToyError::InvalidNode("degenerate corner pin".to_string());
+    if width == 0.0 || height == 0.0 {
+        return Err(degenerate());
+    }
+    let to_unit = three_d::Mat3::from_nonuniform_scale(1.0 / width, 1.0 / height)
+        * three_d::Mat3::from_translation(three_d::vec2(
+            width * 0.5 - center_x,
+            height * 0.5 - center_y,
+        ));
+    let to_canvas_center =
+        three_d::Mat3::from_translation(three_d::vec2(-canvas[0] * 0.5, -canvas[1] * 0.5));
+    Ok(to_canvas_center * square_to_quad(corners).ok_or_else(degenerate)? * to_unit)
+}
+
+/// Projective mapping of the unit square onto a quad, after Heckbert's
+/// "Fundamentals of Texture Mapping and Image Warping"
+fn square_to_quad(quad: &[[f32; 2]; 4]) -> Option<three_d::Mat3> {
+    let [[x0, y0], [x1, y1], [x2, y2], [x3, y3]] = *quad;
+    let (dx1, dx2, dx3) = (x1 - x2, x3 - x2, x0 - x1 + x2 - x3);
+    let (dy1, dy2, dy3) = (y1 - y2, y3 - y2, y0 - y1 + y2 - y3);
+    let det = dx1 * dy2 - dx2 * dy1;
+    if det == 0.0 {
+        return None;
+    }
+    // Zero for parallelograms, where the mapping is affine
+    let g = (dx3 * dy2 - dx2 * dy3) / det;
+    let h = (dx1 * dy3 - dx3 * dy1) / det;
+
+    let matrix = three_d::Mat3::new(
+        x1 - x0 + g * x1,
+        y1 - y0 + g * y1,
+        g,
+        x3 - x0 + h * x3,
+        y3 - y0 + h * y3,
+        h,
+        x0,
+        y0,
+        1.0,
+    );
+    matrix.is_invertible().then_some(matrix)
+}
+
+/// Triangulate a Catmull-Rom surface through the lattice, so the warp is smooth across cells
+fn mesh(
+    columns: u32,
+    rows: u32,
+    points: &[[f32; 2]],
+    subdivisions: u32,
+    uv: [f32; 4],
+    canvas: [f32; 2],
+) -> Result<programs::Mesh, ShaderToyError> {
+    if columns < 2 || rows < 2 || subdivisions == 0 {
+        return Err(ShaderToyError::InvalidNode(
+            "mesh warps need at least 2×2 points and one subdivision".to_string(),
+        ));
+    }
+    if points.len() != (columns * rows) as usize {
+        return Err(ShaderToyError::InvalidNode(format!(
+            "mesh warp of {}×{} needs {} points, got {}",
+            columns,
+            rows,
+            columns * rows,
+            points.len()
+        )));
+    }
+
+    let (columns, rows) = (columns as i64, rows as i64);
+    // Points past the edges repeat the outer ones
+    let point = |column: i64, row: i64| {
+        points[(row.clamp(0, rows - 1) * columns + column.clamp(0, columns - 1)) as usize]
+    };
+    let evaluate = |x: f32, y: f32| {
+        let column = (x.floor() as i64).min(columns - 2);
+        let row = (y.floor() as i64).min(rows - 2);
+        let (s, t) = (x - column as f32, y - row as f32);
+        let along_row = |r: i64| catmull_rom([-1, 0, 1, 2].map(|i| point(column + i, r)), s);
+        catmull_rom([-1, 0, 1, 2].map(|i| along_row(row + i)), t)
+    };
+
+    let steps_x = (columns as u32 - 1) * subdivisions;
+    let steps_y = (rows as u32 - 1) * subdivisions;
+    let vertex = |i: u32, j: u32| {
+        let (fx, fy) = (i as f32 / steps_x as f32, j as f32 / steps_y as f32);
+        let [x, y] = evaluate(fx * (columns - 1) as f32, fy * (rows - 1) as f32);
+        (
+            three_d::vec3(x - canvas[0] * 0.5, y - canvas[1] * 0.5, 1.0),
+            three_d::vec2(uv[0] + (uv[2] - uv[0]) * fx, uv[1] + (uv[3] - uv[1]) * fy),
+        )
+    };
+
+    let mut mesh = programs::Mesh {
+        positions: vec![],
+        uvs: vec![],
+    };
+    for j in 0..steps_y {
+        for i in 0..steps_x {
+            for (ci, cj) in [(0, 1), (0, 0), (1, 0), (0, 1), (1, 0), (1, 1)] {
+                let (position, uv) = vertex(i + ci, j + cj);
+                mesh.positions.push(position);
+                mesh.uvs.push(uv);
+            }
+        }
+    }
+    Ok(mesh)
+}
+
+fn catmull_rom(p: [[f32; 2]; 4], t: f32) -> [f32; 2] {
+    let (t2, t3) = (t * t, t * t * t);
+    let weights = [
+        -0.5 * t3 + t2 - 0.5 * t,
+        1.5 * t3 - 2.5 * t2 + 1.0,
+        -1.5 * t3 + 2.0 * t2 + 0.5 * t,
+        0.5 * t3 - 0.5 * t2,
+    ];
+    let mut result = [0.0, 0.0];
+    for (point, weight) in p.iter().zip(weights) {
+        result[0] += point[0] * weight;
+        result[1] += point[1] * weight;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: [f32; 2], expected: [f32; 2]) {
+        assert!(
+            (actual[0] - expected[0]).abs() < 1e-3 && (actual[1] - expected[1]).abs() < 1e-3,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn apply(matrix: three_d::Mat3, x: f32, y: f32) -> [f32; 2] {
+        let p = matrix * three_d::vec3(x, y, 1.0);
+        [p.x / p.z, p.y / p.z]
+    }
+
+    #[test]
+    fn square_to_quad_maps_corners() {
+        let quad = [[10.0, 20.0], [110.0, 5.0], [130.0, 90.0], [-5.0, 70.0]];
+        let matrix = square_to_quad(&quad).unwrap();
+        for (corner, expected) in [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+            .iter()
+            .zip(quad)
+        {
+            assert_near(apply(matrix, corner[0], corner[1]), expected);
+        }
+    }
+
+    #[test]
+    fn square_to_quad_rejects_degenerate_quads() {
+        let collinear = [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]];
+        assert!(square_to_quad(&collinear).is_none());
+    }
+
+    #[test]
+    fn corner_pin_maps_bounds_to_corners() {
+        let corners = [[10.0, 20.0], [110.0, 5.0], [130.0, 90.0], [-5.0, 70.0]];
+        let canvas = [200.0, 100.0];
+        let matrix = corner_pin(&corners, [0.0, 0.0, 80.0, 40.0], canvas).unwrap();
+        let layer = [[-40.0, -20.0], [40.0, -20.0], [40.0, 20.0], [-40.0, 20.0]];
+        for (point, corner) in layer.iter().zip(corners) {
+            // Corners are from the top-left, the result from the canvas center
+            let expected = [corner[0] - canvas[0] * 0.5, corner[1] - canvas[1] * 0.5];
+            assert_near(apply(matrix, point[0], point[1]), expected);
+        }
+    }
+
+    #[test]
+    fn mesh_passes_through_lattice_points() {
+        let points = [
+            [0.0, 0.0],
+            [60.0, 10.0],
+            [100.0, -5.0],
+            [-10.0, 50.0],
+            [45.0, 40.0],
+            [110.0, 60.0],
+            [5.0, 100.0],
+            [50.0, 120.0],
+            [95.0, 100.0],
+        ];
+        let (subdivisions, canvas) = (4, [100.0, 100.0]);
+        let mesh = mesh(3, 3, &points, subdivisions, [0.0, 0.0, 1.0, 1.0], canvas).unwrap();
+        for (index, point) in points.iter().enumerate() {
+            let uv = three_d::vec2((index % 3) as f32 * 0.5, (index / 3) as f32 * 0.5);
+            let expected = [point[0] - canvas[0] * 0.5, point[1] - canvas[1] * 0.5];
+            let vertex = mesh
+                .uvs
+                .iter()
+                .position(|v| (v - uv).x.abs() < 1e-6 && (v - uv).y.abs() < 1e-6)
+                .map(|i| mesh.positions[i])
+                .unwrap();
+            assert_near([vertex.x, vertex.y], expected);
+        }
+    }
+
+    #[test]
+    fn mesh_rejects_wrong_point_count() {
+        let points = [[0.0, 0.0]; 3];
+        assert!(mesh(2, 2, &points, 1, [0.0, 0.0, 1.0, 1.0], [1.0, 1.0]).is_err());
+    }
+}